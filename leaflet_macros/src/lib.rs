@@ -5,6 +5,7 @@ mod test;
 mod network_type;
 mod packet;
 mod packet_handlers;
+mod nbt;
 
 #[proc_macro]
 pub fn my_proc_macro(input: TokenStream) -> TokenStream {
@@ -36,3 +37,27 @@ pub fn packet_handlers(input: TokenStream) -> TokenStream {
     packet_handlers::expand(input.into()).into()
 }
 
+/// Builds an `NBTTag` from SNBT-like syntax, e.g.
+/// `nbt!{ "coordinate_scale": 1.0d, attributes: {}, values: [I; 1, 2], name: some_string }`.
+///
+/// Number literals take SNBT (`1b`, `2s`, `3l`, `1.5f`, `2d`) or Rust suffixes and are
+/// range checked. Other expressions are converted with `TagContent::from`.
+///
+/// Lists mixing literal types are rejected at compile time:
+///
+/// ```compile_fail
+/// leaflet_nbt::nbt!{ values: [1, "two"] };
+/// ```
+///
+/// Lists holding interpolated values can only be checked at runtime, with
+/// `NBTTag::try_list`, so the macro then returns a `TagResult<NBTTag>`:
+///
+/// ```
+/// let name = "Steve";
+/// let tag = leaflet_nbt::nbt!{ names: [name, "Alex"] }.unwrap();
+/// assert!(leaflet_nbt::nbt!{ values: [name, 2] }.is_err());
+/// ```
+#[proc_macro]
+pub fn nbt(input: TokenStream) -> TokenStream {
+    nbt::expand(input.into()).into()
+}
@@ -0,0 +1,296 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{
+    braced,
+    bracketed,
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    spanned::Spanned,
+    token,
+    Error, Expr, Ident, Lit, LitStr, Result, Token,
+};
+
+const TYPE_NAMES: [&str; 13] = [
+    "end", "byte", "short", "int", "long", "float", "double",
+    "byte array", "string", "list", "compound", "int array", "long array",
+];
+
+/// A parsed value, and its tag type if it is known at compile time.
+struct Value {
+    type_id: Option<u8>,
+    span: Span,
+    content: TokenStream,
+    /// Whether the content contains a list only checked at runtime, with `?`.
+    fallible: bool,
+}
+
+struct Root(Value);
+
+impl Parse for Root {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // A lone `{...}` or `[...]` is the root itself, anything else is the body of a compound
+        let fork = input.fork();
+        if (fork.peek(token::Brace) || fork.peek(token::Bracket)) && parse_value(&fork).is_ok() && fork.is_empty() {
+            return Ok(Root(parse_value(input)?));
+        }
+        Ok(Root(parse_compound_body(input, input.span())?))
+    }
+}
+
+fn parse_compound_body(input: ParseStream, span: Span) -> Result<Value> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut entries = Vec::new();
+    let mut fallible = false;
+    while !input.is_empty() {
+        let key = if input.peek(LitStr) {
+            input.parse::<LitStr>()?
+        } else {
+            let ident = Ident::parse_any(input)?;
+            LitStr::new(&ident.to_string(), ident.span())
+        };
+        if keys.contains(&key.value()) {
+            return Err(Error::new(key.span(), format!("duplicate key `{}`", key.value())));
+        }
+        keys.push(key.value());
+
+        input.parse::<Token![:]>()?;
+        let value = parse_value(input)?;
+        fallible |= value.fallible;
+        let content = value.content;
+        entries.push(quote! { compound.insert(#key, #content); });
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+
+    Ok(Value {
+        type_id: Some(10),
+        span,
+        content: quote! {{
+            #[allow(unused_mut)]
+            let mut compound = ::leaflet_nbt::NbtCompound::new();
+            #(#entries)*
+            ::leaflet_nbt::TagContent::Compound(compound)
+        }},
+        fallible,
+    })
+}
+
+fn parse_value(input: ParseStream) -> Result<Value> {
+    let span = input.span();
+    if input.peek(token::Brace) {
+        let content;
+        braced!(content in input);
+        return parse_compound_body(&content, span);
+    }
+    if input.peek(token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        if content.peek(Ident) && content.peek2(Token![;]) {
+            return parse_array(&content, span);
+        }
+        return parse_list(&content, span);
+    }
+    if input.peek(Lit) || (input.peek(Token![-]) && input.peek2(Lit)) {
+        return parse_literal(input);
+    }
+
+    let expr: Expr = input.parse()?;
+    Ok(Value {
+        type_id: None,
+        span,
+        content: quote_spanned! {expr.span()=> ::leaflet_nbt::TagContent::from(#expr) },
+        fallible: false,
+    })
+}
+
+fn parse_list(input: ParseStream, span: Span) -> Result<Value> {
+    let mut first: Option<u8> = None;
+    let mut items = Vec::new();
+    let mut fallible = false;
+    while !input.is_empty() {
+        let value = parse_value(input)?;
+        // Interpolated values only have a type at runtime
+        fallible |= value.fallible || value.type_id.is_none();
+        match (first, value.type_id) {
+            (None, type_id) => first = type_id,
+            (Some(expected), Some(found)) if expected != found => {
+                return Err(Error::new(
+                    value.span,
+                    format!(
+                        "list elements must all have the same type, expected {} but found {}",
+                        TYPE_NAMES[expected as usize], TYPE_NAMES[found as usize],
+                    ),
+                ));
+            }
+            _ => {}
+        }
+        let content = value.content;
+        items.push(quote! { ::leaflet_nbt::NBTTag { content: #content, name: None } });
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+
+    let content = if fallible {
+        quote! { ::leaflet_nbt::NBTTag::try_list(None, ::std::vec![#(#items),*])?.content }
+    } else {
+        quote! { ::leaflet_nbt::TagContent::List(::std::vec![#(#items),*]) }
+    };
+    Ok(Value { type_id: Some(9), span, content, fallible })
+}
+
+fn parse_array(input: ParseStream, span: Span) -> Result<Value> {
+    let kind: Ident = input.parse()?;
+    input.parse::<Token![;]>()?;
+    let (type_id, element_type, variant) = match kind.to_string().as_str() {
+        "B" => (7, 1, quote!(ByteArray)),
+        "I" => (11, 3, quote!(IntArray)),
+        "L" => (12, 4, quote!(LongArray)),
+        _ => return Err(Error::new(kind.span(), "expected `B`, `I` or `L` for a typed array")),
+    };
+
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        if input.peek(Lit) || (input.peek(Token![-]) && input.peek2(Lit)) {
+            let negative = input.parse::<Option<Token![-]>>()?.is_some();
+            let lit = match input.parse::<Lit>()? {
+                Lit::Int(lit) => lit,
+                other => return Err(Error::new(other.span(), "typed arrays can only hold integers")),
+            };
+            let found = int_type(lit.suffix(), element_type).ok_or_else(|| {
+                Error::new(lit.span(), format!("expected {} elements", TYPE_NAMES[element_type as usize]))
+            })?;
+            if found != element_type {
+                return Err(Error::new(
+                    lit.span(),
+                    format!("expected {} elements, found {}", TYPE_NAMES[element_type as usize], TYPE_NAMES[found as usize]),
+                ));
+            }
+            items.push(int_literal(&lit, negative, element_type)?);
+        } else {
+            let expr: Expr = input.parse()?;
+            items.push(quote!(#expr));
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+
+    Ok(Value {
+        type_id: Some(type_id),
+        span,
+        content: quote! { ::leaflet_nbt::TagContent::#variant(::std::vec![#(#items),*]) },
+        fallible: false,
+    })
+}
+
+/// Tag type named by an integer literal suffix, using SNBT or Rust suffixes.
+fn int_type(suffix: &str, default: u8) -> Option<u8> {
+    match suffix {
+        "" => Some(default),
+        "b" | "B" | "i8" => Some(1),
+        "s" | "S" | "i16" => Some(2),
+        "i32" => Some(3),
+        "l" | "L" | "i64" => Some(4),
+        "f" | "F" | "f32" => Some(5),
+        "d" | "D" | "f64" => Some(6),
+        _ => None,
+    }
+}
+
+/// An integer literal checked to fit the tag type.
+fn int_literal(lit: &syn::LitInt, negative: bool, type_id: u8) -> Result<TokenStream> {
+    let digits = lit.base10_digits();
+    let value: i128 = digits.parse().map_err(|_| Error::new(lit.span(), "integer literal is too large"))?;
+    let value = if negative { -value } else { value };
+    let (min, max, ty) = match type_id {
+        1 => (i8::MIN as i128, i8::MAX as i128, "i8"),
+        2 => (i16::MIN as i128, i16::MAX as i128, "i16"),
+        3 => (i32::MIN as i128, i32::MAX as i128, "i32"),
+        4 => (i64::MIN as i128, i64::MAX as i128, "i64"),
+        5 => return Ok(float_literal(digits, negative, "f32", lit.span())),
+        _ => return Ok(float_literal(digits, negative, "f64", lit.span())),
+    };
+    if value < min || value > max {
+        return Err(Error::new(
+            lit.span(),
+            format!("{value} does not fit in a {}", TYPE_NAMES[type_id as usize]),
+        ));
+    }
+    let lit = syn::LitInt::new(&format!("{value}{ty}"), lit.span());
+    Ok(quote!(#lit))
+}
+
+fn float_literal(digits: &str, negative: bool, ty: &str, span: Span) -> TokenStream {
+    let digits = if digits.contains(['.', 'e', 'E']) { digits.to_string() } else { format!("{digits}.0") };
+    let lit = syn::LitFloat::new(&format!("{digits}{ty}"), span);
+    if negative { quote!(-#lit) } else { quote!(#lit) }
+}
+
+fn parse_literal(input: ParseStream) -> Result<Value> {
+    let span = input.span();
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let lit: Lit = input.parse()?;
+    let (type_id, value) = match &lit {
+        Lit::Int(lit) => {
+            let type_id = int_type(lit.suffix(), 3)
+                .ok_or_else(|| Error::new(lit.span(), format!("unknown suffix `{}`", lit.suffix())))?;
+            (type_id, int_literal(lit, negative, type_id)?)
+        }
+        Lit::Float(lit) => {
+            let (type_id, ty) = match lit.suffix() {
+                "" | "d" | "D" | "f64" => (6, "f64"),
+                "f" | "F" | "f32" => (5, "f32"),
+                suffix => return Err(Error::new(lit.span(), format!("unknown suffix `{suffix}` for a decimal number"))),
+            };
+            (type_id, float_literal(lit.base10_digits(), negative, ty, lit.span()))
+        }
+        Lit::Bool(lit) if !negative => {
+            let value = lit.value as i8;
+            (1, quote!(#value))
+        }
+        Lit::Str(lit) if !negative => (8, quote!(::std::string::String::from(#lit))),
+        other => return Err(Error::new(other.span(), "unsupported literal")),
+    };
+
+    let variant = match type_id {
+        1 => quote!(Byte),
+        2 => quote!(Short),
+        3 => quote!(Int),
+        4 => quote!(Long),
+        5 => quote!(Float),
+        6 => quote!(Double),
+        _ => quote!(String),
+    };
+    Ok(Value {
+        type_id: Some(type_id),
+        span,
+        content: quote! { ::leaflet_nbt::TagContent::#variant(#value) },
+        fallible: false,
+    })
+}
+
+pub fn expand(input: TokenStream) -> TokenStream {
+    match syn::parse2::<Root>(input) {
+        Ok(Root(value)) if value.fallible => {
+            let content = value.content;
+            quote! {
+                (|| -> ::leaflet_nbt::TagResult<::leaflet_nbt::NBTTag> {
+                    Ok(::leaflet_nbt::NBTTag { content: #content, name: None })
+                })()
+            }
+        }
+        Ok(Root(value)) => {
+            let content = value.content;
+            quote! { ::leaflet_nbt::NBTTag { content: #content, name: None } }
+        }
+        Err(e) => e.to_compile_error(),
+    }
+}
@@ -56,7 +56,9 @@ fn generate_read(fields: &Fields) -> TokenStream {
     )
 }
 
-fn generate_write(fields: &Fields) -> TokenStream {
+/// Generates `write`, or `try_write` if `fallible`, which uses `try_write` for
+/// nested types so their errors are passed on.
+fn generate_write(fields: &Fields, fallible: bool) -> TokenStream {
     let write_iter = fields.iter().map(|field| {
         let name = &field.ident;
         let ty = &field.ty;
@@ -84,6 +86,7 @@ fn generate_write(fields: &Fields) -> TokenStream {
                     }
                     Some("i64") => quote!(buf.write_long(self.#name)),
                     Some("String") => quote!(buf.write_string(self.#name.as_str())),
+                    _ if fallible => quote!(buf.try_write_network_type(&self.#name)?),
                     _ => quote!(buf.write_network_type(&self.#name))
                 }
             }
@@ -95,11 +98,20 @@ fn generate_write(fields: &Fields) -> TokenStream {
         )
     });
 
-    quote!(
-        fn write(&self, buf: &mut leaflet_network_buffer::McBuf) {
-            #(#write_iter)*
-        }
-    )
+    if fallible {
+        quote!(
+            fn try_write(&self, buf: &mut leaflet_network_buffer::McBuf) -> leaflet_network_buffer::BufferResult<()> {
+                #(#write_iter)*
+                Ok(())
+            }
+        )
+    } else {
+        quote!(
+            fn write(&self, buf: &mut leaflet_network_buffer::McBuf) {
+                #(#write_iter)*
+            }
+        )
+    }
 }
 
 pub fn network_type_derive_impl(input: DeriveInput) -> TokenStream {
@@ -113,12 +125,14 @@ pub fn network_type_derive_impl(input: DeriveInput) -> TokenStream {
     };
 
     let read = generate_read(fields);
-    let write = generate_write(fields);
+    let write = generate_write(fields, false);
+    let try_write = generate_write(fields, true);
 
     quote!(
         impl NetworkType for #name {
             #read
             #write
+            #try_write
         }
     )
 }
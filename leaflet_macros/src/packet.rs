@@ -1,7 +1,42 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{DeriveInput, LitInt};
+use syn::{DeriveInput, LitInt, Token};
+
+/// `#[packet_id(0x30)]` for a packet with the same id in every version, or
+/// `#[packet_id(V1_21_7 = 0x2B, V1_21_9 = 0x30)]` for one whose id changed.
+/// Each id holds from its version until the next one listed, so versions go
+/// oldest first, and the packet does not exist before the first.
+enum PacketIds {
+    All(LitInt),
+    Since(Vec<(Ident, LitInt)>),
+}
+
+impl Parse for PacketIds {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitInt) {
+            return Ok(PacketIds::All(input.parse()?));
+        }
+
+        let pairs = Punctuated::<VersionId, Token![,]>::parse_terminated(input)?;
+        Ok(PacketIds::Since(pairs.into_iter().map(|p| (p.version, p.id)).collect()))
+    }
+}
+
+struct VersionId {
+    version: Ident,
+    id: LitInt,
+}
+
+impl Parse for VersionId {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let version = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(Self { version, id: input.parse()? })
+    }
+}
 
 pub fn packet_derive_impl(input: DeriveInput) -> TokenStream {
     let name = &input.ident;
@@ -41,16 +76,35 @@ pub fn packet_derive_impl(input: DeriveInput) -> TokenStream {
     let Some(id_attr) = id_attr else {
         return quote_spanned!(input.span() => compile_error!("Missing #[packet_id(...)]"););
     };
-    let id: LitInt = match id_attr.parse_args() {
+    let ids: PacketIds = match id_attr.parse_args() {
         Ok(v) => v,
         Err(e) => return e.to_compile_error(),
     };
+    let id_body = match ids {
+        PacketIds::All(id) => quote!(Some(#id)),
+        PacketIds::Since(pairs) => {
+            let checks = pairs.iter().rev().map(|(version, id)| {
+                quote!(
+                    if version >= crate::version::ProtocolVersion::#version {
+                        return Some(#id);
+                    }
+                )
+            });
+            quote!(
+                #(#checks)*
+                None
+            )
+        }
+    };
 
     quote!(
         impl Packet for #name {
             const STATE: crate::ConnectionState = crate::ConnectionState::#state;
             const DIRECTION: crate::PacketDirection = crate::PacketDirection::#bound;
-            const ID: i32 = #id;
+
+            fn id(version: crate::version::ProtocolVersion) -> Option<i32> {
+                #id_body
+            }
         }
     )
 }
@@ -152,8 +152,8 @@ pub fn expand(input: TokenStream) -> TokenStream {
             let arms = packets.iter().map(|pkt_ty| {
                 let m = method_name_from_packet_path(pkt_ty);
                 quote! {
-                    x if x == <#pkt_ty as Packet>::ID => {
-                        let p = <#pkt_ty as leaflet_network_buffer::NetworkType>::read(buf)?;
+                    x if Some(x) == <#pkt_ty as Packet>::id(version) => {
+                        let p = <#pkt_ty as Packet>::read_versioned(buf, version)?;
                         handler.#m(connection, p)
                     }
                 }
@@ -171,6 +171,7 @@ pub fn expand(input: TokenStream) -> TokenStream {
                 pub fn #fn_name<H: #trait_name>(
                     connection: &mut H::ClientType,
                     buf: &mut leaflet_network_buffer::McBuf,
+                    version: crate::version::ProtocolVersion,
                     handler: &H,
                 ) -> leaflet_network_buffer::BufferResult<()> {
                     let id = buf.read_var_int()?;
@@ -0,0 +1,30 @@
+//! Helpers for `#[serde(with = "...")]` to store sequences as typed array tags
+//! instead of lists. Reading accepts both representations.
+
+macro_rules! typed_array {
+    ($module:ident, $token:path) => {
+        pub mod $module {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: Serialize + ?Sized,
+                S: Serializer,
+            {
+                serializer.serialize_newtype_struct($token, value)
+            }
+
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where
+                T: Deserialize<'de>,
+                D: Deserializer<'de>,
+            {
+                T::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+typed_array!(byte_array, crate::ser::BYTE_ARRAY_TOKEN);
+typed_array!(int_array, crate::ser::INT_ARRAY_TOKEN);
+typed_array!(long_array, crate::ser::LONG_ARRAY_TOKEN);
@@ -0,0 +1,311 @@
+use indexmap::IndexMap;
+use crate::{NBTTag, TagContent};
+
+/// Compound tag contents, keyed by name and kept in insertion order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NbtCompound {
+    entries: IndexMap<String, TagContent>,
+}
+
+/// Borrowed conversion from a tag, used by the typed getters.
+pub trait FromTag<'a>: Sized {
+    /// Name of the expected tag type, for error messages.
+    const TYPE_NAME: &'static str;
+
+    fn from_tag(content: &'a TagContent) -> Option<Self>;
+}
+
+macro_rules! from_tag {
+    ($ty:ty, $variant:ident, $type_name:literal) => {
+        impl<'a> FromTag<'a> for $ty {
+            const TYPE_NAME: &'static str = $type_name;
+
+            fn from_tag(content: &'a TagContent) -> Option<Self> {
+                match content {
+                    TagContent::$variant(v) => Some(*v),
+                    _ => None,
+                }
+            }
+        }
+    };
+    (ref $ty:ty, $variant:ident, $type_name:literal) => {
+        impl<'a> FromTag<'a> for &'a $ty {
+            const TYPE_NAME: &'static str = $type_name;
+
+            fn from_tag(content: &'a TagContent) -> Option<Self> {
+                match content {
+                    TagContent::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+from_tag!(i8, Byte, "byte");
+from_tag!(i16, Short, "short");
+from_tag!(i32, Int, "int");
+from_tag!(i64, Long, "long");
+from_tag!(f32, Float, "float");
+from_tag!(f64, Double, "double");
+from_tag!(ref str, String, "string");
+from_tag!(ref [i8], ByteArray, "byte array");
+from_tag!(ref [NBTTag], List, "list");
+from_tag!(ref NbtCompound, Compound, "compound");
+from_tag!(ref [i32], IntArray, "int array");
+from_tag!(ref [i64], LongArray, "long array");
+
+impl<'a> FromTag<'a> for bool {
+    const TYPE_NAME: &'static str = "byte";
+
+    fn from_tag(content: &'a TagContent) -> Option<Self> {
+        match content {
+            TagContent::Byte(v) => Some(*v != 0),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FromTag<'a> for &'a TagContent {
+    const TYPE_NAME: &'static str = "any tag";
+
+    fn from_tag(content: &'a TagContent) -> Option<Self> {
+        Some(content)
+    }
+}
+
+impl NbtCompound {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Inserts an entry, returning the previous value. Replacing a key keeps its position.
+    pub fn insert(&mut self, key: impl Into<String>, value: TagContent) -> Option<TagContent> {
+        self.entries.insert(key.into(), value)
+    }
+
+    /// Removes an entry, preserving the order of the remaining entries.
+    pub fn remove(&mut self, key: &str) -> Option<TagContent> {
+        self.entries.shift_remove(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&TagContent> {
+        self.entries.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut TagContent> {
+        self.entries.get_mut(key)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&str, &TagContent)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (&str, &mut TagContent)> {
+        self.entries.iter_mut().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &TagContent> {
+        self.entries.values()
+    }
+
+    /// Gets an entry converted to `T`, or `None` if it is missing or has another type.
+    pub fn get_as<'a, T: FromTag<'a>>(&'a self, key: &str) -> Option<T> {
+        self.get(key).and_then(T::from_tag)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get_as(key)
+    }
+
+    pub fn get_byte(&self, key: &str) -> Option<i8> {
+        self.get_as(key)
+    }
+
+    pub fn get_short(&self, key: &str) -> Option<i16> {
+        self.get_as(key)
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i32> {
+        self.get_as(key)
+    }
+
+    pub fn get_long(&self, key: &str) -> Option<i64> {
+        self.get_as(key)
+    }
+
+    pub fn get_float(&self, key: &str) -> Option<f32> {
+        self.get_as(key)
+    }
+
+    pub fn get_double(&self, key: &str) -> Option<f64> {
+        self.get_as(key)
+    }
+
+    pub fn get_string(&self, key: &str) -> Option<&str> {
+        self.get_as(key)
+    }
+
+    pub fn get_byte_array(&self, key: &str) -> Option<&[i8]> {
+        self.get_as(key)
+    }
+
+    pub fn get_int_array(&self, key: &str) -> Option<&[i32]> {
+        self.get_as(key)
+    }
+
+    pub fn get_long_array(&self, key: &str) -> Option<&[i64]> {
+        self.get_as(key)
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<&[NBTTag]> {
+        self.get_as(key)
+    }
+
+    pub fn get_compound(&self, key: &str) -> Option<&NbtCompound> {
+        self.get_as(key)
+    }
+
+    pub fn get_compound_mut(&mut self, key: &str) -> Option<&mut NbtCompound> {
+        match self.get_mut(key) {
+            Some(TagContent::Compound(v)) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Gets a list whose elements all convert to `T`. An empty list converts to
+    /// any element type.
+    pub fn get_list_of<'a, T: FromTag<'a>>(&'a self, key: &str) -> Option<Vec<T>> {
+        self.get_list(key)?
+            .iter()
+            .map(|item| T::from_tag(&item.content))
+            .collect()
+    }
+}
+
+impl FromIterator<(String, TagContent)> for NbtCompound {
+    fn from_iter<I: IntoIterator<Item = (String, TagContent)>>(iter: I) -> Self {
+        Self { entries: iter.into_iter().collect() }
+    }
+}
+
+/// Collects named tags, using the empty string for unnamed ones.
+impl FromIterator<NBTTag> for NbtCompound {
+    fn from_iter<I: IntoIterator<Item = NBTTag>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|tag| (tag.name.unwrap_or_default(), tag.content))
+            .collect()
+    }
+}
+
+impl Extend<(String, TagContent)> for NbtCompound {
+    fn extend<I: IntoIterator<Item = (String, TagContent)>>(&mut self, iter: I) {
+        self.entries.extend(iter)
+    }
+}
+
+impl IntoIterator for NbtCompound {
+    type Item = (String, TagContent);
+    type IntoIter = indexmap::map::IntoIter<String, TagContent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NbtCompound {
+    type Item = (&'a String, &'a TagContent);
+    type IntoIter = indexmap::map::Iter<'a, String, TagContent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NBTTag, TagContent};
+    use super::NbtCompound;
+
+    fn compound() -> NbtCompound {
+        NbtCompound::from_iter([
+            NBTTag::boolean(Some("flag".into()), true),
+            NBTTag::byte(Some("byte".into()), -1),
+            NBTTag::short(Some("short".into()), 300),
+            NBTTag::int(Some("int".into()), 70000),
+            NBTTag::long(Some("long".into()), 1 << 40),
+            NBTTag::float(Some("float".into()), 0.5),
+            NBTTag::double(Some("double".into()), 0.25),
+            NBTTag::string(Some("string".into()), "text".into()),
+            NBTTag::byte_array(Some("bytes".into()), vec![1, 2]),
+            NBTTag::int_array(Some("ints".into()), vec![3]),
+            NBTTag::long_array(Some("longs".into()), vec![4]),
+            NBTTag::list(Some("list".into()), vec![NBTTag::int(None, 5), NBTTag::int(None, 6)]),
+            NBTTag::compound(Some("nested".into()), vec![NBTTag::int(Some("x".into()), 7)]),
+        ])
+    }
+
+    #[test]
+    fn typed_getters_match_their_tag() {
+        let compound = compound();
+        assert_eq!(compound.get_bool("flag"), Some(true));
+        assert_eq!(compound.get_byte("byte"), Some(-1));
+        assert_eq!(compound.get_short("short"), Some(300));
+        assert_eq!(compound.get_int("int"), Some(70000));
+        assert_eq!(compound.get_long("long"), Some(1 << 40));
+        assert_eq!(compound.get_float("float"), Some(0.5));
+        assert_eq!(compound.get_double("double"), Some(0.25));
+        assert_eq!(compound.get_string("string"), Some("text"));
+        assert_eq!(compound.get_byte_array("bytes"), Some(&[1, 2][..]));
+        assert_eq!(compound.get_int_array("ints"), Some(&[3][..]));
+        assert_eq!(compound.get_long_array("longs"), Some(&[4][..]));
+        assert_eq!(compound.get_list("list").map(<[_]>::len), Some(2));
+        assert_eq!(compound.get_list_of::<i32>("list"), Some(vec![5, 6]));
+        assert_eq!(compound.get_compound("nested").and_then(|nested| nested.get_int("x")), Some(7));
+    }
+
+    #[test]
+    fn typed_getters_reject_other_types_and_missing_keys() {
+        let compound = compound();
+        // No widening, even where the value would fit
+        assert_eq!(compound.get_int("short"), None);
+        assert_eq!(compound.get_double("float"), None);
+        assert_eq!(compound.get_bool("int"), None);
+        assert_eq!(compound.get_string("bytes"), None);
+        assert_eq!(compound.get_list_of::<i64>("list"), None);
+        assert_eq!(compound.get_compound("list"), None);
+        assert_eq!(compound.get_int("missing"), None);
+    }
+
+    #[test]
+    fn keeps_insertion_order() {
+        let mut compound = NbtCompound::new();
+        for key in ["zeta", "alpha", "mid"] {
+            compound.insert(key, TagContent::Byte(0));
+        }
+        assert_eq!(compound.keys().collect::<Vec<_>>(), ["zeta", "alpha", "mid"]);
+
+        // Replacing keeps the position, removing closes the gap
+        assert_eq!(compound.insert("zeta", TagContent::Byte(1)), Some(TagContent::Byte(0)));
+        assert_eq!(compound.remove("alpha"), Some(TagContent::Byte(0)));
+        compound.insert("alpha", TagContent::Byte(2));
+        assert_eq!(compound.keys().collect::<Vec<_>>(), ["zeta", "mid", "alpha"]);
+        assert_eq!(compound.get_byte("zeta"), Some(1));
+    }
+}
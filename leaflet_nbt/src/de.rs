@@ -0,0 +1,399 @@
+use std::fmt::Formatter;
+use serde::de::value::{BorrowedStrDeserializer, SeqDeserializer, U8Deserializer};
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+use leaflet_network_buffer::McBuf;
+use crate::error::{SerdeError, SerdeResult};
+use crate::ser::TAG_TOKEN;
+use crate::{NBTTag, NbtCompound, TagContent};
+
+/// Deserializes a value borrowing strings from the given tag where possible.
+pub fn from_nbt<'de, T: Deserialize<'de>>(tag: &'de NBTTag) -> SerdeResult<T> {
+    T::deserialize(NbtDeserializer::new(&tag.content))
+}
+
+/// Reads a network NBT tag from the buffer and deserializes it.
+pub fn from_buf<T: DeserializeOwned>(buf: &mut McBuf) -> SerdeResult<T> {
+    let tag: NBTTag = buf.read_network_type()?;
+    from_nbt(&tag)
+}
+
+pub struct NbtDeserializer<'de> {
+    content: &'de TagContent,
+}
+
+impl<'de> NbtDeserializer<'de> {
+    pub fn new(content: &'de TagContent) -> Self {
+        Self { content }
+    }
+}
+
+impl<'de> Deserializer<'de> for NbtDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::End => visitor.visit_unit(),
+            TagContent::Byte(v) => visitor.visit_i8(*v),
+            TagContent::Short(v) => visitor.visit_i16(*v),
+            TagContent::Int(v) => visitor.visit_i32(*v),
+            TagContent::Long(v) => visitor.visit_i64(*v),
+            TagContent::Float(v) => visitor.visit_f32(*v),
+            TagContent::Double(v) => visitor.visit_f64(*v),
+            TagContent::ByteArray(v) => visit_array(v, visitor),
+            TagContent::String(v) => visitor.visit_borrowed_str(v),
+            TagContent::List(items) => visitor.visit_seq(ListAccess { items: items.iter() }),
+            TagContent::Compound(entries) => visitor.visit_map(CompoundAccess {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            TagContent::IntArray(v) => visit_array(v, visitor),
+            TagContent::LongArray(v) => visit_array(v, visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::Byte(v) => visitor.visit_bool(*v != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::Byte(v) => visitor.visit_u8(*v as u8),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::Short(v) => visitor.visit_u16(*v as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::Int(v) => visitor.visit_u32(*v as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::Long(v) => visitor.visit_u64(*v as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::ByteArray(v) => visitor.visit_byte_buf(v.iter().map(|b| *b as u8).collect()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::End => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        if name == TAG_TOKEN {
+            visitor.visit_enum(TypedTagAccess { content: self.content })
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        match self.content {
+            TagContent::String(variant) => visitor.visit_enum(variant.as_str().into_deserializer()),
+            TagContent::Compound(entries) if entries.len() == 1 => {
+                let (name, content) = entries.iter().next().unwrap();
+                visitor.visit_enum(VariantEntryAccess { name, content })
+            }
+            _ => Err(SerdeError::Custom(
+                "expected a string or a single-entry compound for an enum".into(),
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+fn visit_array<'de, T, V>(values: &'de [T], visitor: V) -> SerdeResult<V::Value>
+where
+    T: Copy + IntoDeserializer<'de, SerdeError>,
+    V: Visitor<'de>,
+{
+    let mut access = SeqDeserializer::new(values.iter().copied());
+    let value = visitor.visit_seq(&mut access)?;
+    access.end()?;
+    Ok(value)
+}
+
+struct ListAccess<'de> {
+    items: std::slice::Iter<'de, NBTTag>,
+}
+
+impl<'de> SeqAccess<'de> for ListAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> SerdeResult<Option<T::Value>> {
+        match self.items.next() {
+            Some(item) => seed.deserialize(NbtDeserializer::new(&item.content)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct CompoundAccess<'de> {
+    entries: <&'de NbtCompound as IntoIterator>::IntoIter,
+    value: Option<&'de TagContent>,
+}
+
+impl<'de> MapAccess<'de> for CompoundAccess<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> SerdeResult<Option<K::Value>> {
+        match self.entries.next() {
+            Some((name, content)) => {
+                self.value = Some(content);
+                seed.deserialize(BorrowedStrDeserializer::new(name)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> SerdeResult<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or(SerdeError::Custom("next_value called before next_key".into()))?;
+        seed.deserialize(NbtDeserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct VariantEntryAccess<'de> {
+    name: &'de str,
+    content: &'de TagContent,
+}
+
+impl<'de> EnumAccess<'de> for VariantEntryAccess<'de> {
+    type Error = SerdeError;
+    type Variant = NbtDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> SerdeResult<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<SerdeError>::new(self.name))?;
+        Ok((variant, NbtDeserializer::new(self.content)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for NbtDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> SerdeResult<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> SerdeResult<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> SerdeResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> SerdeResult<V::Value> {
+        self.deserialize_any(visitor)
+    }
+}
+
+/// Exposes a tag as an enum whose variant is its type id, so that
+/// [`TagContent`]'s own `Deserialize` impl can tell typed arrays from lists.
+struct TypedTagAccess<'de> {
+    content: &'de TagContent,
+}
+
+impl<'de> EnumAccess<'de> for TypedTagAccess<'de> {
+    type Error = SerdeError;
+    type Variant = NbtDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> SerdeResult<(V::Value, Self::Variant)> {
+        let type_id = seed.deserialize(U8Deserializer::<SerdeError>::new(self.content.type_id()))?;
+        Ok((type_id, NbtDeserializer::new(self.content)))
+    }
+}
+
+impl<'de> Deserialize<'de> for TagContent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(TAG_TOKEN, TagContentVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for NBTTag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(NBTTag { content: TagContent::deserialize(deserializer)?, name: None })
+    }
+}
+
+struct TagContentVisitor;
+
+impl<'de> Visitor<'de> for TagContentVisitor {
+    type Value = TagContent;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("any NBT value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<TagContent, E> {
+        Ok(TagContent::Byte(v as i8))
+    }
+
+    fn visit_i8<E>(self, v: i8) -> Result<TagContent, E> {
+        Ok(TagContent::Byte(v))
+    }
+
+    fn visit_i16<E>(self, v: i16) -> Result<TagContent, E> {
+        Ok(TagContent::Short(v))
+    }
+
+    fn visit_i32<E>(self, v: i32) -> Result<TagContent, E> {
+        Ok(TagContent::Int(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<TagContent, E> {
+        Ok(TagContent::Long(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<TagContent, E> {
+        Ok(TagContent::Long(v as i64))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> Result<TagContent, E> {
+        Ok(TagContent::Float(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<TagContent, E> {
+        Ok(TagContent::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<TagContent, E> {
+        Ok(TagContent::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<TagContent, E> {
+        Ok(TagContent::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<TagContent, E> {
+        Ok(TagContent::ByteArray(v.iter().map(|b| *b as i8).collect()))
+    }
+
+    fn visit_unit<E>(self) -> Result<TagContent, E> {
+        Ok(TagContent::End)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<TagContent, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TagContent, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element::<NBTTag>()? {
+            items.push(item);
+        }
+        Ok(TagContent::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<TagContent, A::Error> {
+        CompoundVisitor.visit_map(map).map(TagContent::Compound)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<TagContent, A::Error> {
+        let (type_id, variant): (u8, _) = data.variant()?;
+        match type_id {
+            7 => variant.newtype_variant().map(TagContent::ByteArray),
+            11 => variant.newtype_variant().map(TagContent::IntArray),
+            12 => variant.newtype_variant().map(TagContent::LongArray),
+            _ => variant.newtype_variant_seed(UntypedSeed),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for NbtCompound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(CompoundVisitor)
+    }
+}
+
+struct CompoundVisitor;
+
+impl<'de> Visitor<'de> for CompoundVisitor {
+    type Value = NbtCompound;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        formatter.write_str("an NBT compound")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<NbtCompound, A::Error> {
+        let mut entries = NbtCompound::new();
+        while let Some((name, content)) = map.next_entry::<String, TagContent>()? {
+            entries.insert(name, content);
+        }
+        Ok(entries)
+    }
+}
+
+struct UntypedSeed;
+
+impl<'de> DeserializeSeed<'de> for UntypedSeed {
+    type Value = TagContent;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<TagContent, D::Error> {
+        deserializer.deserialize_any(TagContentVisitor)
+    }
+}
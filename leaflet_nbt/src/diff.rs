@@ -0,0 +1,328 @@
+//! Merging, diffing and patching of tag trees.
+//!
+//! [`TagContent::merge`] follows vanilla's `/data merge`: compounds are merged
+//! key by key and anything else is replaced. [`TagContent::diff`] lists the
+//! added, removed and changed paths between two trees, and [`TagContent::apply`]
+//! replays such a diff onto a tree.
+
+use std::fmt::{Display, Formatter};
+use crate::path::{format_path, PathSegment};
+use crate::{NBTTag, NbtCompound, PathError, PathErrorKind, PathResult, TagContent};
+
+/// A single difference between two trees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The entry or list element exists only in the new tree.
+    Added { path: Vec<PathSegment>, value: TagContent },
+    /// The entry or list element exists only in the old tree.
+    Removed { path: Vec<PathSegment>, value: TagContent },
+    /// The tag differs in type or value. An empty path is the root.
+    Changed { path: Vec<PathSegment>, old: TagContent, new: TagContent },
+}
+
+impl Change {
+    pub fn path(&self) -> &[PathSegment] {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let path = format_path(self.path());
+        let path = if path.is_empty() { "<root>" } else { &path };
+        match self {
+            Change::Added { value, .. } => write!(f, "+ {path}: {}", value.to_snbt()),
+            Change::Removed { value, .. } => write!(f, "- {path}: {}", value.to_snbt()),
+            Change::Changed { old, new, .. } => write!(f, "~ {path}: {} -> {}", old.to_snbt(), new.to_snbt()),
+        }
+    }
+}
+
+/// The changes turning one tree into another, in the order they can be applied.
+///
+/// Printing a diff gives one line per change, with values as SNBT:
+///
+/// ```text
+/// + attributes.fog_color: 12638463
+/// - timelines[1]: "minecraft:night"
+/// ~ coordinate_scale: 1.0d -> 8.0d
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NbtDiff {
+    pub changes: Vec<Change>,
+}
+
+impl NbtDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for NbtDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+fn diff_into(old: &TagContent, new: &TagContent, path: &mut Vec<PathSegment>, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (TagContent::Compound(old), TagContent::Compound(new)) => {
+            for (key, old_value) in old.iter() {
+                path.push(PathSegment::Key(key.to_string()));
+                match new.get(key) {
+                    Some(new_value) => diff_into(old_value, new_value, path, changes),
+                    None => changes.push(Change::Removed { path: path.clone(), value: old_value.clone() }),
+                }
+                path.pop();
+            }
+            for (key, new_value) in new.iter().filter(|(key, _)| !old.contains_key(key)) {
+                path.push(PathSegment::Key(key.to_string()));
+                changes.push(Change::Added { path: path.clone(), value: new_value.clone() });
+                path.pop();
+            }
+        }
+        // Lists are compared element by element unless their element type changed
+        (TagContent::List(old_items), TagContent::List(new_items))
+            if old_items.is_empty()
+                || new_items.is_empty()
+                || old_items[0].content.type_id() == new_items[0].content.type_id() =>
+        {
+            for (index, (old, new)) in old_items.iter().zip(new_items).enumerate() {
+                path.push(PathSegment::Index(index));
+                diff_into(&old.content, &new.content, path, changes);
+                path.pop();
+            }
+            // Removals go from the back, so earlier indices stay valid while applying
+            for index in (new_items.len()..old_items.len()).rev() {
+                path.push(PathSegment::Index(index));
+                changes.push(Change::Removed { path: path.clone(), value: old_items[index].content.clone() });
+                path.pop();
+            }
+            for (index, item) in new_items.iter().enumerate().skip(old_items.len()) {
+                path.push(PathSegment::Index(index));
+                changes.push(Change::Added { path: path.clone(), value: item.content.clone() });
+                path.pop();
+            }
+        }
+        (old, new) if old != new => changes.push(Change::Changed { path: path.clone(), old: old.clone(), new: new.clone() }),
+        _ => {}
+    }
+}
+
+fn error(path: &[PathSegment], kind: PathErrorKind) -> PathError {
+    PathError { path: format_path(path), kind }
+}
+
+fn wrong_type(path: &[PathSegment], expected: &'static str, found: &TagContent) -> PathError {
+    error(path, PathErrorKind::WrongType { expected, found: found.type_name() })
+}
+
+/// Walks to the tag at `path`.
+fn resolve<'a>(root: &'a mut TagContent, path: &[PathSegment]) -> PathResult<&'a mut TagContent> {
+    let mut current = root;
+    for (depth, segment) in path.iter().enumerate() {
+        let prefix = &path[..depth + 1];
+        current = match (current, segment) {
+            (TagContent::Compound(compound), PathSegment::Key(key)) => {
+                compound.get_mut(key).ok_or_else(|| error(prefix, PathErrorKind::Missing))?
+            }
+            (TagContent::List(items), PathSegment::Index(index)) => {
+                let len = items.len();
+                &mut items
+                    .get_mut(*index)
+                    .ok_or_else(|| error(prefix, PathErrorKind::IndexOutOfBounds { index: *index, len }))?
+                    .content
+            }
+            (other, PathSegment::Key(_)) => return Err(wrong_type(&path[..depth], "compound", other)),
+            (other, PathSegment::Index(_)) => return Err(wrong_type(&path[..depth], "list", other)),
+        };
+    }
+    Ok(current)
+}
+
+fn apply_change(root: &mut TagContent, change: &Change) -> PathResult<()> {
+    let path = change.path();
+    let Some((last, parent_path)) = path.split_last() else {
+        // Only a change can target the root
+        return match change {
+            Change::Changed { old, new, .. } if root == old => {
+                *root = new.clone();
+                Ok(())
+            }
+            _ => Err(error(path, PathErrorKind::Conflict)),
+        };
+    };
+
+    let parent = resolve(root, parent_path)?;
+    match (parent, last, change) {
+        (TagContent::Compound(compound), PathSegment::Key(key), Change::Added { value, .. }) => {
+            if compound.contains_key(key) {
+                return Err(error(path, PathErrorKind::Conflict));
+            }
+            compound.insert(key.clone(), value.clone());
+        }
+        (TagContent::Compound(compound), PathSegment::Key(key), Change::Removed { value, .. }) => {
+            match compound.get(key) {
+                Some(current) if current == value => compound.remove(key),
+                Some(_) => return Err(error(path, PathErrorKind::Conflict)),
+                None => return Err(error(path, PathErrorKind::Missing)),
+            };
+        }
+        (TagContent::List(items), &PathSegment::Index(index), Change::Added { value, .. }) => {
+            if index > items.len() {
+                return Err(error(path, PathErrorKind::IndexOutOfBounds { index, len: items.len() }));
+            }
+            items.insert(index, NBTTag { content: value.clone(), name: None });
+        }
+        (TagContent::List(items), &PathSegment::Index(index), Change::Removed { value, .. }) => {
+            match items.get(index) {
+                Some(current) if current.content == *value => items.remove(index),
+                Some(_) => return Err(error(path, PathErrorKind::Conflict)),
+                None => return Err(error(path, PathErrorKind::IndexOutOfBounds { index, len: items.len() })),
+            };
+        }
+        (parent, last, Change::Changed { old, new, .. }) => {
+            let current = resolve(parent, std::slice::from_ref(last))?;
+            if current != old {
+                return Err(error(path, PathErrorKind::Conflict));
+            }
+            *current = new.clone();
+        }
+        (parent, PathSegment::Key(_), _) => return Err(wrong_type(parent_path, "compound", parent)),
+        (parent, PathSegment::Index(_), _) => return Err(wrong_type(parent_path, "list", parent)),
+    }
+    Ok(())
+}
+
+impl NbtCompound {
+    /// Deep merges `other` into this compound. Nested compounds are merged,
+    /// all other entries of `other` replace or extend the existing ones.
+    pub fn merge(&mut self, other: &NbtCompound) {
+        for (key, value) in other.iter() {
+            match (self.get_mut(key), value) {
+                (Some(TagContent::Compound(existing)), TagContent::Compound(value)) => existing.merge(value),
+                _ => {
+                    self.insert(key, value.clone());
+                }
+            }
+        }
+    }
+}
+
+impl TagContent {
+    /// Merges `other` into this tag like `/data merge`. Two compounds are merged
+    /// with [`NbtCompound::merge`], anything else is replaced by `other`.
+    pub fn merge(&mut self, other: &TagContent) {
+        match (self, other) {
+            (TagContent::Compound(existing), TagContent::Compound(other)) => existing.merge(other),
+            (this, other) => *this = other.clone(),
+        }
+    }
+
+    /// Lists the changes that turn this tag into `other`.
+    pub fn diff(&self, other: &TagContent) -> NbtDiff {
+        let mut changes = Vec::new();
+        diff_into(self, other, &mut Vec::new(), &mut changes);
+        NbtDiff { changes }
+    }
+
+    /// Applies a diff made by [`TagContent::diff`]. Every change must find the
+    /// old value it recorded, otherwise this fails with [`PathErrorKind::Conflict`]
+    /// and the tag is left untouched.
+    pub fn apply(&mut self, diff: &NbtDiff) -> PathResult<()> {
+        let mut patched = self.clone();
+        for change in &diff.changes {
+            apply_change(&mut patched, change)?;
+        }
+        *self = patched;
+        Ok(())
+    }
+}
+
+impl NBTTag {
+    /// See [`TagContent::merge`]. The name is kept.
+    pub fn merge(&mut self, other: &NBTTag) {
+        self.content.merge(&other.content)
+    }
+
+    /// See [`TagContent::diff`]. Names are not compared.
+    pub fn diff(&self, other: &NBTTag) -> NbtDiff {
+        self.content.diff(&other.content)
+    }
+
+    /// See [`TagContent::apply`].
+    pub fn apply(&mut self, diff: &NbtDiff) -> PathResult<()> {
+        self.content.apply(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NBTTag, PathErrorKind, TagContent};
+    use super::Change;
+
+    fn snbt(value: &str) -> TagContent {
+        NBTTag::from_snbt(value).unwrap().content
+    }
+
+    #[test]
+    fn applying_a_diff_gives_the_new_tree() {
+        let pairs = [
+            ("{a: 1, b: {c: 2, d: [1, 2, 3]}, e: \"x\"}", "{a: 1, b: {c: 3, d: [1, 5], f: 1b}, g: [I; 1]}"),
+            ("{list: [{a: 1}, {b: 2}]}", "{list: [{a: 1}, {b: 2}, {c: 3}, {d: 4}]}"),
+            ("{list: [1, 2, 3, 4]}", "{list: []}"),
+            ("{list: [1, 2]}", "{list: [\"a\", \"b\"]}"),
+            ("{a: 1b}", "{a: 1s}"),
+            ("{a: 1}", "[1, 2]"),
+            ("{}", "{}"),
+        ];
+        for (old, new) in pairs {
+            let (old, new) = (snbt(old), snbt(new));
+            let diff = old.diff(&new);
+            let mut patched = old.clone();
+            patched.apply(&diff).unwrap();
+            assert_eq!(patched, new, "{diff}");
+            assert_eq!(diff.is_empty(), old == new);
+        }
+    }
+
+    #[test]
+    fn lists_changes_by_path() {
+        let diff = snbt("{a: 1, b: [1, 2, 3], c: {d: 1}}").diff(&snbt("{a: 2, b: [1], c: {}, e: 1b}"));
+        assert_eq!(diff.to_string(), ["~ a: 1 -> 2", "- b[2]: 3", "- b[1]: 2", "- c.d: 1", "+ e: 1b"].join("\n"));
+        assert!(matches!(&diff.changes[0], Change::Changed { old: TagContent::Int(1), new: TagContent::Int(2), .. }));
+    }
+
+    #[test]
+    fn conflicting_diffs_leave_the_tree_untouched() {
+        let diff = snbt("{a: 1, b: [1, 2]}").diff(&snbt("{a: 2, b: [1]}"));
+        let mut other = snbt("{a: 1, b: [1, 3]}");
+        let error = other.apply(&diff).unwrap_err();
+        assert_eq!(error.kind, PathErrorKind::Conflict);
+        assert_eq!(error.path, "b[1]");
+        assert_eq!(other, snbt("{a: 1, b: [1, 3]}"));
+    }
+
+    #[test]
+    fn merges_compounds_deeply_and_replaces_everything_else() {
+        let mut tag = snbt("{a: 1, nested: {keep: 1b, deeper: {x: 1, y: 2}, list: [1, 2, 3]}, list: [{a: 1}], replaced: {z: 1}}");
+        tag.merge(&snbt("{b: 2, nested: {deeper: {y: 3, z: 4}, list: [4], added: \"new\"}, list: [{b: 2}], replaced: 5}"));
+        assert_eq!(
+            tag,
+            snbt("{a: 1, nested: {keep: 1b, deeper: {x: 1, y: 3, z: 4}, list: [4], added: \"new\"}, list: [{b: 2}], replaced: 5, b: 2}"),
+        );
+
+        // Anything but two compounds is replaced outright
+        let mut tag = snbt("[1, 2]");
+        tag.merge(&snbt("{a: 1}"));
+        assert_eq!(tag, snbt("{a: 1}"));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::{Display, Formatter};
+use leaflet_network_buffer::BufferError;
+
+#[derive(Debug)]
+pub enum SerdeError {
+    Custom(String),
+    Buffer(BufferError),
+    UnsupportedType(&'static str),
+    KeyMustBeString,
+    NoneInList,
+    ListTypeMismatch { expected: u8, found: u8 },
+    InvalidArray(&'static str),
+    EmptyRoot,
+    Invalid(TagError),
+}
+
+pub type SerdeResult<T> = Result<T, SerdeError>;
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerdeError::Custom(message) => write!(f, "{message}"),
+            SerdeError::Buffer(e) => write!(f, "buffer error: {e:?}"),
+            SerdeError::UnsupportedType(ty) => write!(f, "{ty} cannot be represented in NBT"),
+            SerdeError::KeyMustBeString => write!(f, "compound keys must be strings"),
+            SerdeError::NoneInList => write!(f, "lists cannot contain None or unit values"),
+            SerdeError::ListTypeMismatch { expected, found } => {
+                write!(f, "list item type mismatch: expected tag {expected}, found tag {found}")
+            }
+            SerdeError::InvalidArray(kind) => write!(f, "{kind} elements have the wrong type"),
+            SerdeError::EmptyRoot => write!(f, "root value serialized to nothing"),
+            SerdeError::Invalid(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl From<BufferError> for SerdeError {
+    fn from(value: BufferError) -> Self {
+        SerdeError::Buffer(value)
+    }
+}
+
+impl From<TagError> for SerdeError {
+    fn from(value: TagError) -> Self {
+        SerdeError::Invalid(value)
+    }
+}
+
+impl serde::ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Custom(msg.to_string())
+    }
+}
+
+impl serde::de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Custom(msg.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub enum NbtIoError {
+    Io(std::io::Error),
+    Buffer(BufferError),
+    Invalid(TagError),
+}
+
+pub type NbtIoResult<T> = Result<T, NbtIoError>;
+
+impl Display for NbtIoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NbtIoError::Io(e) => write!(f, "I/O error: {e}"),
+            NbtIoError::Buffer(e) => write!(f, "buffer error: {e:?}"),
+            NbtIoError::Invalid(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for NbtIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NbtIoError::Io(e) => Some(e),
+            NbtIoError::Buffer(_) => None,
+            NbtIoError::Invalid(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for NbtIoError {
+    fn from(value: std::io::Error) -> Self {
+        NbtIoError::Io(value)
+    }
+}
+
+impl From<BufferError> for NbtIoError {
+    fn from(value: BufferError) -> Self {
+        NbtIoError::Buffer(value)
+    }
+}
+
+impl From<TagError> for NbtIoError {
+    fn from(value: TagError) -> Self {
+        NbtIoError::Invalid(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagErrorKind {
+    /// A list element has a different type than the first element.
+    ListTypeMismatch { expected: &'static str, found: &'static str },
+    /// A list element has a name, which the format has no room for.
+    NamedListElement(String),
+    /// An end tag anywhere but the root would terminate its compound early.
+    MisplacedEnd,
+    /// A string or name is too long for its length prefix once encoded.
+    StringTooLong(usize),
+    /// An array or list has more elements than its length prefix can hold.
+    TooManyElements(usize),
+}
+
+/// A tag that cannot be written. `path` points at the offending tag in the
+/// syntax accepted by [`TagContent::query`](crate::TagContent::query), and is
+/// empty for the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagError {
+    pub path: String,
+    pub kind: TagErrorKind,
+}
+
+pub type TagResult<T> = Result<T, TagError>;
+
+impl Display for TagError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TagErrorKind::ListTypeMismatch { expected, found } => {
+                write!(f, "list item at `{}` is a {found}, expected {expected}", self.path)
+            }
+            TagErrorKind::NamedListElement(name) => {
+                write!(f, "list item at `{}` has name `{name}`, but list items are unnamed", self.path)
+            }
+            TagErrorKind::MisplacedEnd => write!(f, "end tag at `{}` is only allowed as the root", self.path),
+            TagErrorKind::StringTooLong(len) => {
+                write!(f, "string at `{}` is {len} bytes long, too long for its length prefix", self.path)
+            }
+            TagErrorKind::TooManyElements(len) => write!(f, "{len} elements at `{}` do not fit in an int", self.path),
+        }
+    }
+}
+
+impl std::error::Error for TagError {}
+
+impl From<TagError> for BufferError {
+    fn from(value: TagError) -> Self {
+        BufferError::Invalid(value.to_string())
+    }
+}
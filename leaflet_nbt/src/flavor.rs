@@ -0,0 +1,140 @@
+use std::borrow::Cow;
+use leaflet_network_buffer::{BufferError, BufferResult, McBuf};
+use crate::mutf8;
+
+/// The binary encodings of NBT. All flavors share the same tag types and
+/// structure, so any tree can be written in any of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NbtFlavor {
+    /// Java Edition: big-endian numbers and Modified UTF-8 strings.
+    #[default]
+    Java,
+    /// Bedrock Edition on disk: little-endian numbers and UTF-8 strings.
+    BedrockDisk,
+    /// Bedrock Edition network protocol: like [`NbtFlavor::BedrockDisk`], but ints,
+    /// longs and lengths are zigzag varints and strings have varint lengths.
+    BedrockNetwork,
+}
+
+fn zigzag_32(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn zigzag_64(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_var_u64(buf: &mut McBuf, mut value: u64) {
+    while value >= 0x80 {
+        buf.write_u8((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.write_u8(value as u8)
+}
+
+impl NbtFlavor {
+    /// Encoded size of a single value of this type, if all values have the same size.
+    pub(crate) fn fixed_size(self, type_id: u8) -> Option<usize> {
+        match (type_id, self) {
+            (3 | 4, NbtFlavor::BedrockNetwork) => None,
+            (0, _) => Some(0),
+            (1, _) => Some(1),
+            (2, _) => Some(2),
+            (3 | 5, _) => Some(4),
+            (4 | 6, _) => Some(8),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn string_len(self, value: &str) -> usize {
+        match self {
+            NbtFlavor::Java => mutf8::encoded_len(value),
+            _ => value.len(),
+        }
+    }
+
+    /// Longest string the length prefix can describe.
+    pub(crate) fn max_string_len(self) -> usize {
+        match self {
+            NbtFlavor::BedrockNetwork => i32::MAX as usize,
+            _ => mutf8::MAX_LENGTH,
+        }
+    }
+
+    pub(crate) fn encode_string(self, value: &str) -> BufferResult<Cow<'_, [u8]>> {
+        match self {
+            NbtFlavor::Java => mutf8::encode(value),
+            _ if value.len() > self.max_string_len() => Err(BufferError::StringTooLong),
+            _ => Ok(Cow::Borrowed(value.as_bytes())),
+        }
+    }
+
+    pub(crate) fn decode_string(self, bytes: &[u8]) -> BufferResult<Cow<'_, str>> {
+        match self {
+            NbtFlavor::Java => mutf8::decode(bytes),
+            _ => match std::str::from_utf8(bytes) {
+                Ok(value) => Ok(Cow::Borrowed(value)),
+                Err(_) => Err(BufferError::StringError(String::from_utf8(bytes.to_vec()).unwrap_err())),
+            },
+        }
+    }
+
+    pub(crate) fn write_short(self, buf: &mut McBuf, value: i16) {
+        match self {
+            NbtFlavor::Java => buf.write_short(value),
+            _ => buf.write_slice(&value.to_le_bytes()),
+        }
+    }
+
+    pub(crate) fn write_int(self, buf: &mut McBuf, value: i32) {
+        match self {
+            NbtFlavor::Java => buf.write_int(value),
+            NbtFlavor::BedrockDisk => buf.write_slice(&value.to_le_bytes()),
+            NbtFlavor::BedrockNetwork => write_var_u64(buf, zigzag_32(value) as u64),
+        }
+    }
+
+    pub(crate) fn write_long(self, buf: &mut McBuf, value: i64) {
+        match self {
+            NbtFlavor::Java => buf.write_long(value),
+            NbtFlavor::BedrockDisk => buf.write_slice(&value.to_le_bytes()),
+            NbtFlavor::BedrockNetwork => write_var_u64(buf, zigzag_64(value)),
+        }
+    }
+
+    pub(crate) fn write_float(self, buf: &mut McBuf, value: f32) {
+        match self {
+            NbtFlavor::Java => buf.write_float(value),
+            _ => buf.write_slice(&value.to_le_bytes()),
+        }
+    }
+
+    pub(crate) fn write_double(self, buf: &mut McBuf, value: f64) {
+        match self {
+            NbtFlavor::Java => buf.write_double(value),
+            _ => buf.write_slice(&value.to_le_bytes()),
+        }
+    }
+
+    /// Writes an array or list length, which is encoded like an int.
+    pub(crate) fn write_length(self, buf: &mut McBuf, len: usize) {
+        self.write_int(buf, len as i32)
+    }
+
+    /// Writes a string with its length prefix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string is too long for the prefix. [`crate::TagContent::validate`]
+    /// and the `try_write` methods report such strings as errors instead.
+    pub(crate) fn write_string(self, buf: &mut McBuf, value: &str) {
+        let bytes = self.encode_string(value).expect("NBT string is too long for its length prefix");
+        match self {
+            NbtFlavor::Java => buf.write_ushort(bytes.len() as u16),
+            NbtFlavor::BedrockDisk => buf.write_slice(&(bytes.len() as u16).to_le_bytes()),
+            NbtFlavor::BedrockNetwork => write_var_u64(buf, bytes.len() as u64),
+        }
+        buf.write_slice(&bytes);
+    }
+}
+
@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use leaflet_network_buffer::McBuf;
+use crate::error::NbtIoResult;
+use crate::{NBTTag, NbtLimits};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl Compression {
+    /// Guesses the compression from the first bytes of a stream.
+    pub fn detect(header: &[u8]) -> Self {
+        match header {
+            [0x1F, 0x8B, ..] => Compression::Gzip,
+            [0x78, second, ..] if (0x7800u16 | *second as u16).is_multiple_of(31) => Compression::Zlib,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// How the root tag is framed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootFormat {
+    /// Type id, name and content, as used by `level.dat`, player data and structure files.
+    Named,
+    /// Type id and content only, as used by the network protocol.
+    Network,
+}
+
+/// Reads a single tag from a stream with [`NbtLimits::FILE`], decompressing it first.
+pub fn read<R: Read>(reader: R, compression: Compression, root: RootFormat) -> NbtIoResult<NBTTag> {
+    read_limited(reader, compression, root, NbtLimits::FILE)
+}
+
+pub fn read_limited<R: Read>(reader: R, compression: Compression, root: RootFormat, limits: NbtLimits) -> NbtIoResult<NBTTag> {
+    // Never decompress more than the tag is allowed to take up
+    let max = limits.max_bytes.saturating_add(1) as u64;
+    let mut data = Vec::new();
+    match compression {
+        Compression::None => BufReader::new(reader).take(max).read_to_end(&mut data)?,
+        Compression::Gzip => GzDecoder::new(reader).take(max).read_to_end(&mut data)?,
+        Compression::Zlib => ZlibDecoder::new(reader).take(max).read_to_end(&mut data)?,
+    };
+
+    let mut buf = McBuf::from_bytes(&data);
+    let tag = match root {
+        RootFormat::Named => NBTTag::read_named_limited(&mut buf, limits)?,
+        RootFormat::Network => NBTTag::read_network_limited(&mut buf, limits)?,
+    };
+    Ok(tag)
+}
+
+/// Writes a single tag to a stream, compressing it.
+pub fn write<W: Write>(writer: W, tag: &NBTTag, compression: Compression, root: RootFormat) -> NbtIoResult<()> {
+    let mut buf = McBuf::new();
+    match root {
+        RootFormat::Named => tag.try_write_named(&mut buf)?,
+        RootFormat::Network => tag.try_write(&mut buf)?,
+    }
+
+    match compression {
+        Compression::None => {
+            let mut writer = writer;
+            writer.write_all(buf.as_slice())?;
+            writer.flush()?;
+        }
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+            encoder.write_all(buf.as_slice())?;
+            encoder.finish()?.flush()?;
+        }
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(writer, flate2::Compression::default());
+            encoder.write_all(buf.as_slice())?;
+            encoder.finish()?.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a named-root NBT file such as `level.dat`.
+pub fn read_file(path: impl AsRef<Path>, compression: Compression) -> NbtIoResult<NBTTag> {
+    read(File::open(path)?, compression, RootFormat::Named)
+}
+
+/// Writes a named-root NBT file such as `level.dat`.
+pub fn write_file(path: impl AsRef<Path>, tag: &NBTTag, compression: Compression) -> NbtIoResult<()> {
+    write(BufWriter::new(File::create(path)?), tag, compression, RootFormat::Named)
+}
+
+#[cfg(test)]
+mod tests {
+    use leaflet_network_buffer::BufferError;
+    use crate::error::NbtIoError;
+    use crate::{NBTTag, NbtLimits};
+    use super::{read, read_limited, write, Compression, RootFormat};
+
+    fn level() -> NBTTag {
+        NBTTag::compound(Some("".into()), vec![
+            NBTTag::compound(Some("Data".into()), vec![
+                NBTTag::string(Some("LevelName".into()), "world".into()),
+                NBTTag::long(Some("Time".into()), 24000),
+                NBTTag::long_array(Some("Seeds".into()), vec![1, -1]),
+            ]),
+        ])
+    }
+
+    fn encode(tag: &NBTTag, compression: Compression, root: RootFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        write(&mut data, tag, compression, root).unwrap();
+        data
+    }
+
+    #[test]
+    fn round_trips_every_compression() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zlib] {
+            for root in [RootFormat::Named, RootFormat::Network] {
+                let data = encode(&level(), compression, root);
+                let tag = read(data.as_slice(), compression, root).unwrap();
+                assert_eq!(tag.content, level().content);
+            }
+        }
+    }
+
+    #[test]
+    fn detects_compression_from_the_header() {
+        for compression in [Compression::None, Compression::Gzip, Compression::Zlib] {
+            let data = encode(&level(), compression, RootFormat::Named);
+            assert_eq!(Compression::detect(&data), compression);
+        }
+        assert_eq!(Compression::detect(&[]), Compression::None);
+        assert_eq!(Compression::detect(&[0x1F]), Compression::None);
+        // 0x78 0x00 fails the zlib header checksum
+        assert_eq!(Compression::detect(&[0x78, 0x00]), Compression::None);
+    }
+
+    #[test]
+    fn reads_files_with_file_limits() {
+        // More entries than the network allows are fine on disk
+        let bytes: Vec<_> = (0..NbtLimits::NETWORK.max_elements + 1).map(|_| NBTTag::byte(None, 0)).collect();
+        let data = encode(&NBTTag::list(Some("".into()), bytes.clone()), Compression::Zlib, RootFormat::Named);
+        assert!(read(data.as_slice(), Compression::Zlib, RootFormat::Named).is_ok());
+
+        // But not more than a file may hold
+        let bytes = (0..NbtLimits::FILE.max_elements + 1).map(|_| NBTTag::byte(None, 0)).collect();
+        let data = encode(&NBTTag::list(Some("".into()), bytes), Compression::Zlib, RootFormat::Named);
+        let result = read(data.as_slice(), Compression::Zlib, RootFormat::Named);
+        assert!(matches!(result, Err(NbtIoError::Buffer(BufferError::ElementLimitExceeded))));
+    }
+
+    #[test]
+    fn stops_decompressing_at_the_size_limit() {
+        let limits = NbtLimits { max_bytes: 64, ..NbtLimits::FILE };
+        let data = encode(&NBTTag::byte_array(Some("".into()), vec![0; 4096]), Compression::Gzip, RootFormat::Named);
+        // Highly compressible, so the compressed input itself is tiny
+        assert!(data.len() < 64);
+        let result = read_limited(data.as_slice(), Compression::Gzip, RootFormat::Named, limits);
+        assert!(matches!(result, Err(NbtIoError::Buffer(BufferError::SizeLimitExceeded))));
+    }
+}
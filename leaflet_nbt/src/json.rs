@@ -0,0 +1,255 @@
+//! Conversion between NBT and JSON, following the rules vanilla uses when
+//! loading data packs:
+//!
+//! - Whole numbers become the smallest of byte, short, int and long that holds
+//!   them, other numbers a float if that is exact and a double otherwise.
+//! - Booleans become bytes. `null` values are dropped from objects and arrays,
+//!   and become an end tag at the root.
+//! - Lists of only bytes, ints or longs become typed arrays.
+//! - Lists mixing types wrap each non-compound element in a compound under
+//!   the empty key, e.g. `[1, "a"]` becomes `[{"": 1b}, {"": "a"}]`.
+//!
+//! Converting back unwraps such elements again. Bytes stay numbers, since
+//! booleans cannot be told apart from them.
+
+use serde_json::{Map, Number, Value};
+use crate::{NBTTag, NbtCompound, TagContent};
+
+fn from_number(number: &Number) -> TagContent {
+    let whole = match (number.as_i64(), number.as_f64()) {
+        (Some(value), _) => Some(value),
+        // Values like 1.0 or 1e3 are whole numbers too
+        (None, Some(value)) if value.fract() == 0.0 && value.abs() < i64::MAX as f64 => Some(value as i64),
+        _ => None,
+    };
+
+    if let Some(value) = whole {
+        return if let Ok(value) = i8::try_from(value) {
+            TagContent::Byte(value)
+        } else if let Ok(value) = i16::try_from(value) {
+            TagContent::Short(value)
+        } else if let Ok(value) = i32::try_from(value) {
+            TagContent::Int(value)
+        } else {
+            TagContent::Long(value)
+        };
+    }
+
+    let value = number.as_f64().unwrap_or(f64::NAN);
+    if value as f32 as f64 == value {
+        TagContent::Float(value as f32)
+    } else {
+        TagContent::Double(value)
+    }
+}
+
+fn from_array(values: &[Value]) -> TagContent {
+    let items: Vec<TagContent> = values
+        .iter()
+        .map(TagContent::from_json)
+        .filter(|item| *item != TagContent::End)
+        .collect();
+    let Some(first) = items.first() else {
+        return TagContent::List(Vec::new());
+    };
+
+    let type_id = first.type_id();
+    if items.iter().all(|item| item.type_id() == type_id) {
+        return match type_id {
+            1 => TagContent::ByteArray(items.into_iter().filter_map(|item| match item {
+                TagContent::Byte(value) => Some(value),
+                _ => None,
+            }).collect()),
+            3 => TagContent::IntArray(items.into_iter().filter_map(|item| match item {
+                TagContent::Int(value) => Some(value),
+                _ => None,
+            }).collect()),
+            4 => TagContent::LongArray(items.into_iter().filter_map(|item| match item {
+                TagContent::Long(value) => Some(value),
+                _ => None,
+            }).collect()),
+            _ => TagContent::List(items.into_iter().map(|content| NBTTag { content, name: None }).collect()),
+        };
+    }
+
+    TagContent::List(
+        items
+            .into_iter()
+            .map(|content| {
+                let content = match content {
+                    TagContent::Compound(compound) if !is_wrapper(&compound) => TagContent::Compound(compound),
+                    other => TagContent::Compound(NbtCompound::from_iter([(String::new(), other)])),
+                };
+                NBTTag { content, name: None }
+            })
+            .collect(),
+    )
+}
+
+/// A compound holding a single list element of another type.
+fn is_wrapper(compound: &NbtCompound) -> bool {
+    compound.len() == 1 && compound.contains_key("")
+}
+
+fn float_to_json(value: f64) -> Value {
+    Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+}
+
+impl TagContent {
+    /// Converts a JSON value using vanilla's rules, see the [module docs](crate::json).
+    pub fn from_json(value: &Value) -> TagContent {
+        match value {
+            Value::Null => TagContent::End,
+            Value::Bool(value) => TagContent::Byte(*value as i8),
+            Value::Number(number) => from_number(number),
+            Value::String(value) => TagContent::String(value.clone()),
+            Value::Array(values) => from_array(values),
+            Value::Object(entries) => TagContent::Compound(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), TagContent::from_json(value)))
+                    .filter(|(_, value)| *value != TagContent::End)
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts the tag to JSON, mainly for debugging. Non-finite floats become `null`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            TagContent::End => Value::Null,
+            TagContent::Byte(value) => Value::from(*value),
+            TagContent::Short(value) => Value::from(*value),
+            TagContent::Int(value) => Value::from(*value),
+            TagContent::Long(value) => Value::from(*value),
+            // Go through the shortest decimal form, so 0.1f stays 0.1 rather than 0.10000000149011612
+            TagContent::Float(value) => float_to_json(value.to_string().parse().unwrap_or(f64::NAN)),
+            TagContent::Double(value) => float_to_json(*value),
+            TagContent::ByteArray(values) => values.iter().copied().map(Value::from).collect(),
+            TagContent::String(value) => Value::String(value.clone()),
+            TagContent::List(items) => items
+                .iter()
+                .map(|item| match &item.content {
+                    TagContent::Compound(compound) if is_wrapper(compound) => compound.get("").unwrap().to_json(),
+                    content => content.to_json(),
+                })
+                .collect(),
+            TagContent::Compound(compound) => Value::Object(
+                compound
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_json()))
+                    .collect::<Map<_, _>>(),
+            ),
+            TagContent::IntArray(values) => values.iter().copied().map(Value::from).collect(),
+            TagContent::LongArray(values) => values.iter().copied().map(Value::from).collect(),
+        }
+    }
+}
+
+impl NBTTag {
+    /// Converts a JSON value to a nameless tag. See [`TagContent::from_json`].
+    pub fn from_json(value: &Value) -> NBTTag {
+        NBTTag { content: TagContent::from_json(value), name: None }
+    }
+
+    /// See [`TagContent::to_json`]. The name is dropped.
+    pub fn to_json(&self) -> Value {
+        self.content.to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use crate::{NBTTag, NbtCompound, TagContent};
+
+    fn convert(value: Value) -> TagContent {
+        TagContent::from_json(&value)
+    }
+
+    fn wrapped(content: TagContent) -> NBTTag {
+        NBTTag { content: TagContent::Compound(NbtCompound::from_iter([(String::new(), content)])), name: None }
+    }
+
+    #[test]
+    fn narrows_numbers() {
+        assert_eq!(convert(json!(1)), TagContent::Byte(1));
+        assert_eq!(convert(json!(-128)), TagContent::Byte(-128));
+        assert_eq!(convert(json!(200)), TagContent::Short(200));
+        assert_eq!(convert(json!(70000)), TagContent::Int(70000));
+        assert_eq!(convert(json!(1i64 << 40)), TagContent::Long(1 << 40));
+        // Whole numbers written as decimals narrow too
+        assert_eq!(convert(json!(1.0)), TagContent::Byte(1));
+        assert_eq!(convert(json!(1e3)), TagContent::Short(1000));
+        assert_eq!(convert(json!(0.5)), TagContent::Float(0.5));
+        assert_eq!(convert(json!(0.1)), TagContent::Double(0.1));
+        assert_eq!(convert(json!(u64::MAX)), TagContent::Float(u64::MAX as f32));
+    }
+
+    #[test]
+    fn makes_typed_arrays_of_bytes_ints_and_longs() {
+        assert_eq!(convert(json!([1, 2])), TagContent::ByteArray(vec![1, 2]));
+        assert_eq!(convert(json!([70000, 70001])), TagContent::IntArray(vec![70000, 70001]));
+        assert_eq!(convert(json!([1i64 << 40])), TagContent::LongArray(vec![1 << 40]));
+        assert_eq!(convert(json!([300, 301])), TagContent::List(vec![NBTTag::short(None, 300), NBTTag::short(None, 301)]));
+        assert_eq!(convert(json!(["a", "b"])), TagContent::List(vec![NBTTag::string(None, "a".into()), NBTTag::string(None, "b".into())]));
+        assert_eq!(convert(json!([])), TagContent::List(vec![]));
+    }
+
+    #[test]
+    fn wraps_mixed_lists() {
+        // Numbers of different widths are mixed types too
+        assert_eq!(convert(json!([1, 300])), TagContent::List(vec![wrapped(TagContent::Byte(1)), wrapped(TagContent::Short(300))]));
+
+        let mixed = convert(json!([{"a": 1}, "b", {"": 2}]));
+        let TagContent::List(items) = &mixed else { panic!("not a list: {mixed:?}") };
+        assert_eq!(items[0].content.query_as::<i8>("a"), Ok(1));
+        assert_eq!(items[1], wrapped(TagContent::String("b".into())));
+        // A compound that looks like a wrapper is wrapped itself, so it unwraps to the same value
+        assert_eq!(items[2], wrapped(convert(json!({"": 2}))));
+        assert!(mixed.validate().is_ok());
+        assert_eq!(mixed.to_json(), json!([{"a": 1}, "b", {"": 2}]));
+    }
+
+    #[test]
+    fn stores_booleans_as_bytes() {
+        assert_eq!(convert(json!(true)), TagContent::Byte(1));
+        assert_eq!(convert(json!(false)), TagContent::Byte(0));
+        assert_eq!(convert(json!([true, false])), TagContent::ByteArray(vec![1, 0]));
+        // They come back as numbers
+        assert_eq!(convert(json!({"flag": true})).to_json(), json!({"flag": 1}));
+    }
+
+    #[test]
+    fn drops_nulls() {
+        assert_eq!(convert(json!(null)), TagContent::End);
+        assert_eq!(convert(json!({"a": null, "b": 1})).to_json(), json!({"b": 1}));
+        assert_eq!(convert(json!([null, "a"])), TagContent::List(vec![NBTTag::string(None, "a".into())]));
+    }
+
+    #[test]
+    fn round_trips_data_pack_json() {
+        let value = json!({
+            "ambient_light": 0.0,
+            "coordinate_scale": 8.0,
+            "effects": "minecraft:the_nether",
+            "height": 256,
+            "infiniburn": "#minecraft:infiniburn_nether",
+            "monster_spawn_light_level": {"type": "minecraft:uniform", "min_inclusive": 0, "max_inclusive": 7},
+            "fixed_time": 18000,
+            "scale": 0.25,
+        });
+        let tag = NBTTag::from_json(&value);
+        assert!(tag.validate().is_ok());
+        assert_eq!(tag.to_json(), json!({
+            "ambient_light": 0,
+            "coordinate_scale": 8,
+            "effects": "minecraft:the_nether",
+            "height": 256,
+            "infiniburn": "#minecraft:infiniburn_nether",
+            "monster_spawn_light_level": {"type": "minecraft:uniform", "min_inclusive": 0, "max_inclusive": 7},
+            "fixed_time": 18000,
+            "scale": 0.25,
+        }));
+    }
+}
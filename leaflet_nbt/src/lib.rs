@@ -1,6 +1,42 @@
-use leaflet_network_buffer::{BufferError, BufferResult, McBuf, NetworkType};
+use leaflet_network_buffer::{BufferResult, McBuf, NetworkType};
+use crate::visit::TreeBuilder;
 
-#[derive(Debug)]
+// Lets the `::leaflet_nbt` paths generated by `nbt!` resolve in this crate's tests
+extern crate self as leaflet_nbt;
+
+pub mod array;
+mod compound;
+mod de;
+pub mod diff;
+mod error;
+mod flavor;
+pub mod io;
+pub mod json;
+mod limits;
+pub mod mutf8;
+mod path;
+mod reader;
+mod ser;
+pub mod snbt;
+mod validate;
+pub mod view;
+pub mod visit;
+
+pub use de::{from_buf, from_nbt, NbtDeserializer};
+pub use compound::{FromTag, NbtCompound};
+pub use diff::{Change, NbtDiff};
+pub use error::{NbtIoError, NbtIoResult, SerdeError, SerdeResult, TagError, TagErrorKind, TagResult};
+pub use flavor::NbtFlavor;
+pub use io::{Compression, RootFormat};
+pub use limits::NbtLimits;
+pub use path::{PathError, PathErrorKind, PathResult, PathSegment};
+pub use ser::{to_buf, to_nbt, NbtSerializer};
+pub use snbt::{SnbtError, SnbtResult};
+pub use view::{NbtRef, NbtStr};
+pub use visit::{NbtVisitor, Visit};
+pub use leaflet_macros::nbt;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TagContent {
     End,
     Byte(i8),
@@ -12,12 +48,12 @@ pub enum TagContent {
     ByteArray(Vec<i8>),
     String(String),
     List(Vec<NBTTag>),
-    Compound(Vec<NBTTag>),
+    Compound(NbtCompound),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NBTTag {
     pub content: TagContent,
     pub name: Option<String>,
@@ -27,7 +63,7 @@ impl NBTTag {
     pub fn boolean(name: Option<String>, value: bool) -> Self {
         Self {
             content: TagContent::Byte(if value { 1 } else { 0 }),
-            name: name.into(),
+            name,
         }
     }
     
@@ -87,8 +123,8 @@ impl NBTTag {
         }
     }
     
+    /// Builds a list tag without checking its elements. See [`NBTTag::try_list`].
     pub fn list(name: Option<String>, tags: Vec<NBTTag>) -> Self {
-        // TODO: Check all tags have the same type
         Self {
             content: TagContent::List(tags),
             name,
@@ -97,7 +133,7 @@ impl NBTTag {
     
     pub fn compound(name: Option<String>, tags: Vec<NBTTag>) -> Self {
         Self {
-            content: TagContent::Compound(tags),
+            content: TagContent::Compound(tags.into_iter().collect()),
             name,
         }
     }
@@ -136,149 +172,168 @@ impl TagContent {
         }
     }
 
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TagContent::End => "end",
+            TagContent::Byte(_) => "byte",
+            TagContent::Short(_) => "short",
+            TagContent::Int(_) => "int",
+            TagContent::Long(_) => "long",
+            TagContent::Float(_) => "float",
+            TagContent::Double(_) => "double",
+            TagContent::ByteArray(_) => "byte array",
+            TagContent::String(_) => "string",
+            TagContent::List(_) => "list",
+            TagContent::Compound(_) => "compound",
+            TagContent::IntArray(_) => "int array",
+            TagContent::LongArray(_) => "long array",
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&NbtCompound> {
+        match self {
+            TagContent::Compound(value) => Some(value),
+            _ => None,
+        }
+    }
+
     pub fn write_id(&self, buf: &mut McBuf) {
         buf.write_u8(self.type_id())
     }
 
+    /// Writes the content without validating it first.
+    ///
+    /// # Panics
+    ///
+    /// Panics partway through if the tag is invalid, see [`TagContent::validate`].
+    /// Prefer [`NBTTag::try_write`] for tags built from untrusted data.
     pub fn write_content(&self, buf: &mut McBuf) {
+        self.write_content_as(buf, NbtFlavor::Java)
+    }
+
+    /// Writes the content in the given flavor. Panics like [`TagContent::write_content`].
+    pub fn write_content_as(&self, buf: &mut McBuf, flavor: NbtFlavor) {
         match self {
             TagContent::End => {}
             TagContent::Byte(value) => buf.write_byte(*value),
-            TagContent::Short(value) => buf.write_short(*value),
-            TagContent::Int(value) => buf.write_int(*value),
-            TagContent::Long(value) => buf.write_long(*value),
-            TagContent::Float(value) => buf.write_float(*value),
-            TagContent::Double(value) => buf.write_double(*value),
+            TagContent::Short(value) => flavor.write_short(buf, *value),
+            TagContent::Int(value) => flavor.write_int(buf, *value),
+            TagContent::Long(value) => flavor.write_long(buf, *value),
+            TagContent::Float(value) => flavor.write_float(buf, *value),
+            TagContent::Double(value) => flavor.write_double(buf, *value),
             TagContent::ByteArray(value) => {
-                buf.write_int(value.len() as i32);
+                flavor.write_length(buf, value.len());
                 for item in value {
                     buf.write_byte(*item);
                 }
             }
-            TagContent::String(value) => {
-                let bytes = value.as_bytes();
-                buf.write_ushort(bytes.len() as u16);
-                buf.write_slice(bytes);
-            }
+            TagContent::String(value) => flavor.write_string(buf, value),
             TagContent::List(value) => {
                 if value.is_empty() {
                     buf.write_u8(0);
-                    buf.write_int(0);
+                    flavor.write_length(buf, 0);
                     return;
                 }
 
                 let type_id = value[0].content.type_id();
                 buf.write_u8(type_id);
-                buf.write_int(value.len() as i32);
+                flavor.write_length(buf, value.len());
                 for item in value {
                     if item.content.type_id() != type_id {
-                        panic!("List item type mismatch")
+                        panic!("List item type mismatch, validate tags before writing them")
                     }
-                    item.write_name_and_content(buf);
+                    item.content.write_content_as(buf, flavor);
                 }
             }
             TagContent::Compound(value) => {
-                for item in value {
-                    item.content.write_id(buf);
-                    item.write_name_and_content(buf);
+                for (name, content) in value {
+                    content.write_id(buf);
+                    flavor.write_string(buf, name);
+                    content.write_content_as(buf, flavor);
                 }
                 buf.write_u8(0);
             }
             TagContent::IntArray(value) => {
-                buf.write_int(value.len() as i32);
+                flavor.write_length(buf, value.len());
                 for item in value {
-                    buf.write_int(*item);
+                    flavor.write_int(buf, *item);
                 }
             }
             TagContent::LongArray(value) => {
-                buf.write_int(value.len() as i32);
+                flavor.write_length(buf, value.len());
                 for item in value {
-                    buf.write_long(*item);
+                    flavor.write_long(buf, *item);
                 }
             }
         }
     }
 
+    /// Reads tag content with the default [`NbtLimits`].
     pub fn read_content(type_id: u8, buf: &mut McBuf) -> BufferResult<TagContent> {
-        match type_id {
-            0 => Ok(TagContent::End),
-            1 => Ok(TagContent::Byte(buf.read_byte()?)),
-            2 => Ok(TagContent::Short(buf.read_short()?)),
-            3 => Ok(TagContent::Int(buf.read_int()?)),
-            4 => Ok(TagContent::Long(buf.read_long()?)),
-            5 => Ok(TagContent::Float(buf.read_float()?)),
-            6 => Ok(TagContent::Double(buf.read_double()?)),
-            7 => {
-                let len = buf.read_int()? as usize;
-                let mut result = Vec::with_capacity(len);
-                for _ in 0..len {
-                    result.push(buf.read_byte()?);
-                }
-                Ok(TagContent::ByteArray(result))
-            }
-            8 => {
-                let len = buf.read_short()? as usize;
-                Ok(TagContent::String(
-                    String::from_utf8(buf.read_dyn_array(len)?)
-                        .map_err(|e| BufferError::StringError(e))?,
-                ))
-            }
-            9 => {
-                let type_id = buf.read_u8()?;
-                let len = buf.read_int()? as usize;
-                let mut result = Vec::with_capacity(len);
-                for _ in 0..len {
-                    let name = NBTTag::read_name(buf)?;
-                    let content = TagContent::read_content(type_id, buf)?;
-                    result.push(NBTTag { content, name });
-                }
-                Ok(TagContent::List(result))
-            }
-            10 => {
-                let mut result = Vec::new();
-                loop {
-                    let type_id = buf.read_u8()?;
-                    if type_id == 0 {
-                        break
-                    }
+        TagContent::read_content_limited(type_id, buf, NbtLimits::default())
+    }
 
-                    let name = NBTTag::read_name(buf)?;
-                    let content = TagContent::read_content(type_id, buf)?;
-                    result.push(NBTTag { content, name });
-                }
-                Ok(TagContent::Compound(result))
-            }
-            11 => {
-                let len = buf.read_int()? as usize;
-                let mut result = Vec::with_capacity(len);
-                for _ in 0..len {
-                    result.push(buf.read_int()?);
-                }
-                Ok(TagContent::IntArray(result))
-            }
-            12 => {
-                let len = buf.read_int()? as usize;
-                let mut result = Vec::with_capacity(len);
-                for _ in 0..len {
-                    result.push(buf.read_long()?);
+    pub fn read_content_limited(type_id: u8, buf: &mut McBuf, limits: NbtLimits) -> BufferResult<TagContent> {
+        let mut builder = TreeBuilder::default();
+        visit::visit_content(type_id, buf, &mut builder, limits)?;
+        Ok(builder.finish().content)
+    }
+
+    pub fn read_content_as(type_id: u8, buf: &mut McBuf, flavor: NbtFlavor, limits: NbtLimits) -> BufferResult<TagContent> {
+        let mut builder = TreeBuilder::default();
+        visit::visit_content_as(type_id, buf, &mut builder, flavor, limits)?;
+        Ok(builder.finish().content)
+    }
+}
+
+macro_rules! content_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for TagContent {
+                fn from(value: $ty) -> Self {
+                    TagContent::$variant(value)
                 }
-                Ok(TagContent::LongArray(result))
             }
-            _ => Err(BufferError::Generic("Unknown tag type")),
-        }
+        )*
+    };
+}
+
+content_from! {
+    i8 => Byte,
+    i16 => Short,
+    i32 => Int,
+    i64 => Long,
+    f32 => Float,
+    f64 => Double,
+    Vec<i8> => ByteArray,
+    String => String,
+    Vec<NBTTag> => List,
+    NbtCompound => Compound,
+    Vec<i32> => IntArray,
+    Vec<i64> => LongArray,
+}
+
+impl From<bool> for TagContent {
+    fn from(value: bool) -> Self {
+        TagContent::Byte(value as i8)
+    }
+}
+
+impl From<&str> for TagContent {
+    fn from(value: &str) -> Self {
+        TagContent::String(value.to_string())
+    }
+}
+
+impl From<NBTTag> for TagContent {
+    fn from(tag: NBTTag) -> Self {
+        tag.content
     }
 }
 
 impl NBTTag {
     pub fn write_name(&self, buf: &mut McBuf) {
-        match &self.name {
-            Some(name) => {
-                let bytes = name.as_bytes();
-                buf.write_ushort(bytes.len() as u16);
-                buf.write_slice(bytes);
-            }
-            None => buf.write_ushort(0),
-        }
+        NbtFlavor::Java.write_string(buf, self.name.as_deref().unwrap_or(""))
     }
 
     pub fn write_name_and_content(&self, buf: &mut McBuf) {
@@ -288,30 +343,143 @@ impl NBTTag {
 
     pub fn read_name(buf: &mut McBuf) -> BufferResult<Option<String>> {
         let len = buf.read_ushort()? as usize;
-        if len == 0 {
+        let name = mutf8::decode(&buf.read_dyn_array(len)?)?.into_owned();
+        if name.is_empty() {
             Ok(None)
         } else {
-            Ok(Some(
-                String::from_utf8(buf.read_dyn_array(len)?)
-                    .map_err(|e| BufferError::StringError(e))?
-            ))
+            Ok(Some(name))
         }
     }
+
+    /// Reads a tag with a named root, as used by NBT files, with [`NbtLimits::FILE`].
+    pub fn read_named(buf: &mut McBuf) -> BufferResult<Self> {
+        NBTTag::read_named_limited(buf, NbtLimits::FILE)
+    }
+
+    pub fn read_named_limited(buf: &mut McBuf, limits: NbtLimits) -> BufferResult<Self> {
+        let mut builder = TreeBuilder::default();
+        visit::visit_named(buf, &mut builder, limits)?;
+        Ok(builder.finish())
+    }
+
+    /// Reads a tag with a named root in any flavor. Bedrock always uses named roots.
+    pub fn read_named_as(buf: &mut McBuf, flavor: NbtFlavor, limits: NbtLimits) -> BufferResult<Self> {
+        let mut builder = TreeBuilder::default();
+        visit::visit_named_as(buf, &mut builder, flavor, limits)?;
+        Ok(builder.finish())
+    }
+
+    /// Reads a tag with a nameless network root.
+    pub fn read_network_limited(buf: &mut McBuf, limits: NbtLimits) -> BufferResult<Self> {
+        let mut builder = TreeBuilder::default();
+        visit::visit_network(buf, &mut builder, limits)?;
+        Ok(builder.finish())
+    }
+
+    /// Writes the tag with a named root, as used by NBT files.
+    pub fn write_named(&self, buf: &mut McBuf) {
+        self.write_named_as(buf, NbtFlavor::Java)
+    }
+
+    /// Writes the tag with a named root in any flavor.
+    pub fn write_named_as(&self, buf: &mut McBuf, flavor: NbtFlavor) {
+        self.content.write_id(buf);
+        flavor.write_string(buf, self.name.as_deref().unwrap_or(""));
+        self.content.write_content_as(buf, flavor);
+    }
 }
 
+/// Network NBT: a nameless root, as sent since 1.20.2. Reads with [`NbtLimits::NETWORK`].
+///
+/// `write` panics if the tag is invalid, while `try_write` checks it first, so
+/// packets built from plugin data should be sent with the latter.
 impl NetworkType for NBTTag {
     fn read(buf: &mut McBuf) -> BufferResult<Self> {
-        let type_id = buf.read_u8()?;
-        let name = NBTTag::read_name(buf)?;
-        let content = TagContent::read_content(type_id, buf)?;
-        Ok(NBTTag {
-            content,
-            name,
-        })
+        NBTTag::read_network_limited(buf, NbtLimits::NETWORK)
     }
 
     fn write(&self, buf: &mut McBuf) {
         self.content.write_id(buf);
         self.content.write_content(buf);
     }
+
+    fn try_write(&self, buf: &mut McBuf) -> BufferResult<()> {
+        Ok(NBTTag::try_write(self, buf)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{nbt, NBTTag, TagContent, TagErrorKind};
+
+    #[test]
+    fn nbt_macro_matches_hand_built_tags() {
+        let tag = nbt!{
+            "coordinate_scale": 1.0d,
+            has_skylight: true,
+            ambient_light: 0.5f,
+            min_y: -64,
+            height: 384s,
+            seed: 42l,
+            flags: 3b,
+            effects: "minecraft:overworld",
+            attributes: {},
+            timelines: [],
+            heights: [I; 1, -2],
+            bytes: [B; 1, 2b],
+            longs: [L; 3],
+            nested: [{a: 1}, {b: [1.5f, 2f]}],
+        };
+        let expected = NBTTag::compound(None, vec![
+            NBTTag::double(Some("coordinate_scale".into()), 1.0),
+            NBTTag::boolean(Some("has_skylight".into()), true),
+            NBTTag::float(Some("ambient_light".into()), 0.5),
+            NBTTag::int(Some("min_y".into()), -64),
+            NBTTag::short(Some("height".into()), 384),
+            NBTTag::long(Some("seed".into()), 42),
+            NBTTag::byte(Some("flags".into()), 3),
+            NBTTag::string(Some("effects".into()), "minecraft:overworld".into()),
+            NBTTag::compound(Some("attributes".into()), vec![]),
+            NBTTag::list(Some("timelines".into()), vec![]),
+            NBTTag::int_array(Some("heights".into()), vec![1, -2]),
+            NBTTag::byte_array(Some("bytes".into()), vec![1, 2]),
+            NBTTag::long_array(Some("longs".into()), vec![3]),
+            NBTTag::list(Some("nested".into()), vec![
+                NBTTag::compound(None, vec![NBTTag::int(Some("a".into()), 1)]),
+                NBTTag::compound(None, vec![NBTTag::list(Some("b".into()), vec![NBTTag::float(None, 1.5), NBTTag::float(None, 2.0)])]),
+            ]),
+        ]);
+        assert_eq!(tag.content, expected.content);
+        assert_eq!(tag.name, None);
+    }
+
+    #[test]
+    fn nbt_macro_interpolates_expressions() {
+        let skybox = String::from("end");
+        let light = 7;
+        let tag = nbt!{ skybox: skybox.clone(), light: light, ids: [I; light, light * 2], inner: { scale: f64::from(light) / 14.0 } };
+        let expected = NBTTag::compound(None, vec![
+            NBTTag::string(Some("skybox".into()), skybox),
+            NBTTag::int(Some("light".into()), 7),
+            NBTTag::int_array(Some("ids".into()), vec![7, 14]),
+            NBTTag::compound(Some("inner".into()), vec![NBTTag::double(Some("scale".into()), 0.5)]),
+        ]);
+        assert_eq!(tag.content, expected.content);
+
+        // A lone list or compound is the root itself
+        assert_eq!(nbt!([1b, 2b]).content, NBTTag::list(None, vec![NBTTag::byte(None, 1), NBTTag::byte(None, 2)]).content);
+        assert_eq!(nbt!({}).content, TagContent::Compound(Default::default()));
+    }
+
+    #[test]
+    fn nbt_macro_checks_interpolated_lists() {
+        let name = "Alex";
+        let tag = nbt!{ names: ["Steve", name], nested: { more: [name] } }.unwrap();
+        assert_eq!(tag.query_as::<&str>("names[1]"), Ok("Alex"));
+        assert_eq!(tag.query_as::<&str>("nested.more[0]"), Ok("Alex"));
+
+        let count = 2i8;
+        let error = nbt!{ outer: { values: [name, count] } }.unwrap_err();
+        assert!(matches!(error.kind, TagErrorKind::ListTypeMismatch { expected: "string", found: "byte" }));
+    }
 }
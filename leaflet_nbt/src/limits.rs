@@ -0,0 +1,95 @@
+use leaflet_network_buffer::{BufferError, BufferResult};
+
+/// Bounds applied while decoding NBT, so untrusted input cannot exhaust the
+/// stack or memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NbtLimits {
+    /// Maximum nesting of lists and compounds below the root.
+    pub max_depth: usize,
+    /// Maximum number of bytes the encoded tag may take up.
+    pub max_bytes: usize,
+    /// Maximum number of tags in all lists and compounds combined.
+    pub max_elements: usize,
+}
+
+impl NbtLimits {
+    /// Limits for NBT received over the network. Depth and size match vanilla.
+    pub const NETWORK: NbtLimits = NbtLimits {
+        max_depth: 512,
+        max_bytes: 2 * 1024 * 1024,
+        max_elements: 256 * 1024,
+    };
+
+    /// Limits for NBT read from disk, which may hold whole chunks.
+    pub const FILE: NbtLimits = NbtLimits {
+        max_depth: 512,
+        max_bytes: 64 * 1024 * 1024,
+        max_elements: 4 * 1024 * 1024,
+    };
+
+    pub const UNLIMITED: NbtLimits = NbtLimits {
+        max_depth: usize::MAX,
+        max_bytes: usize::MAX,
+        max_elements: usize::MAX,
+    };
+}
+
+impl Default for NbtLimits {
+    fn default() -> Self {
+        NbtLimits::NETWORK
+    }
+}
+
+/// Tracks usage against [`NbtLimits`] while a single tag is decoded. Progress
+/// is measured by how many input bytes remain, so it works for any reader.
+pub(crate) struct NbtAccounter {
+    limits: NbtLimits,
+    start_remaining: usize,
+    depth: usize,
+    elements: usize,
+}
+
+impl NbtAccounter {
+    pub(crate) fn new(limits: NbtLimits, remaining: usize) -> Self {
+        Self { limits, start_remaining: remaining, depth: 0, elements: 0 }
+    }
+
+    /// An accounter that never fails, for walking input that was already checked.
+    pub(crate) fn unlimited() -> Self {
+        NbtAccounter::new(NbtLimits::UNLIMITED, usize::MAX)
+    }
+
+    /// Checks that `additional` more bytes can be read, before anything is allocated for them.
+    pub(crate) fn reserve_bytes(&self, remaining: usize, additional: usize) -> BufferResult<()> {
+        // The limit comes first, since input cut off at the limit would otherwise look truncated
+        let consumed = self.start_remaining - remaining;
+        if consumed.saturating_add(additional) > self.limits.max_bytes {
+            return Err(BufferError::SizeLimitExceeded);
+        }
+        if additional > remaining {
+            return Err(BufferError::BufferUnderflow);
+        }
+        Ok(())
+    }
+
+    /// Counts `count` more list or compound entries, also checking the bytes read so far.
+    pub(crate) fn count_elements(&mut self, remaining: usize, count: usize) -> BufferResult<()> {
+        self.elements = self.elements.saturating_add(count);
+        if self.elements > self.limits.max_elements {
+            return Err(BufferError::ElementLimitExceeded);
+        }
+        self.reserve_bytes(remaining, 0)
+    }
+
+    pub(crate) fn push_depth(&mut self) -> BufferResult<()> {
+        if self.depth >= self.limits.max_depth {
+            return Err(BufferError::DepthLimitExceeded);
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn pop_depth(&mut self) {
+        self.depth -= 1;
+    }
+}
@@ -0,0 +1,155 @@
+//! Java's Modified UTF-8, used for all NBT strings. It differs from UTF-8 in
+//! encoding NUL as two bytes and supplementary characters as a pair of
+//! three-byte surrogates.
+
+use std::borrow::Cow;
+use leaflet_network_buffer::{BufferError, BufferResult};
+
+/// Strings are prefixed with an unsigned short byte length.
+pub const MAX_LENGTH: usize = u16::MAX as usize;
+
+fn is_plain_ascii(bytes: &[u8]) -> bool {
+    bytes.iter().all(|b| *b != 0 && *b < 0x80)
+}
+
+/// Number of bytes the string takes up once encoded.
+pub fn encoded_len(value: &str) -> usize {
+    value
+        .chars()
+        .map(|c| match c as u32 {
+            0 => 2,
+            0x01..0x80 => 1,
+            0x80..0x800 => 2,
+            0x800..0x10000 => 3,
+            _ => 6,
+        })
+        .sum()
+}
+
+/// Encodes a string, failing if the result would not fit the 65535 byte limit.
+pub fn encode(value: &str) -> BufferResult<Cow<'_, [u8]>> {
+    if is_plain_ascii(value.as_bytes()) {
+        return if value.len() > MAX_LENGTH {
+            Err(BufferError::StringTooLong)
+        } else {
+            Ok(Cow::Borrowed(value.as_bytes()))
+        };
+    }
+
+    if encoded_len(value) > MAX_LENGTH {
+        return Err(BufferError::StringTooLong);
+    }
+
+    let mut result = Vec::with_capacity(encoded_len(value));
+    let mut units = [0u16; 2];
+    for c in value.chars() {
+        for unit in c.encode_utf16(&mut units) {
+            let unit = *unit as u32;
+            match unit {
+                0x01..0x80 => result.push(unit as u8),
+                0x00 | 0x80..0x800 => {
+                    result.push(0xC0 | (unit >> 6) as u8);
+                    result.push(0x80 | (unit & 0x3F) as u8);
+                }
+                _ => {
+                    result.push(0xE0 | (unit >> 12) as u8);
+                    result.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                    result.push(0x80 | (unit & 0x3F) as u8);
+                }
+            }
+        }
+    }
+    Ok(Cow::Owned(result))
+}
+
+/// Decodes a string. Unpaired surrogates, which Java allows but Rust strings
+/// cannot hold, are replaced with U+FFFD.
+pub fn decode(bytes: &[u8]) -> BufferResult<Cow<'_, str>> {
+    if is_plain_ascii(bytes) {
+        // Plain ASCII is valid UTF-8
+        return Ok(Cow::Borrowed(std::str::from_utf8(bytes).unwrap()));
+    }
+
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let first = bytes[i] as u16;
+        let continuation = |offset: usize| -> BufferResult<u16> {
+            match bytes.get(i + offset) {
+                Some(b) if b & 0xC0 == 0x80 => Ok((b & 0x3F) as u16),
+                _ => Err(BufferError::InvalidModifiedUtf8),
+            }
+        };
+        match first {
+            0x00..0x80 => {
+                units.push(first);
+                i += 1;
+            }
+            0xC0..0xE0 => {
+                units.push(((first & 0x1F) << 6) | continuation(1)?);
+                i += 2;
+            }
+            0xE0..0xF0 => {
+                units.push(((first & 0x0F) << 12) | (continuation(1)? << 6) | continuation(2)?);
+                i += 3;
+            }
+            _ => return Err(BufferError::InvalidModifiedUtf8),
+        }
+    }
+    Ok(Cow::Owned(String::from_utf16_lossy(&units)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use leaflet_network_buffer::BufferError;
+    use super::{decode, encode, encoded_len, MAX_LENGTH};
+
+    #[test]
+    fn encodes_nul_as_two_bytes() {
+        assert_eq!(encode("a\0b").unwrap().as_ref(), [b'a', 0xC0, 0x80, b'b']);
+        assert_eq!(encoded_len("\0"), 2);
+        assert_eq!(decode(&[0xC0, 0x80]).unwrap(), "\0");
+    }
+
+    #[test]
+    fn encodes_supplementary_characters_as_surrogate_pairs() {
+        // U+1F600 is the surrogate pair D83D DE00, each encoded on its own
+        let encoded = encode("\u{1F600}").unwrap();
+        assert_eq!(encoded.as_ref(), [0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]);
+        assert_eq!(encoded_len("\u{1F600}"), 6);
+        assert_eq!(decode(&encoded).unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn round_trips_and_borrows_plain_ascii() {
+        assert!(matches!(encode("minecraft:stone").unwrap(), Cow::Borrowed(_)));
+        assert!(matches!(decode(b"minecraft:stone").unwrap(), Cow::Borrowed(_)));
+        for value in ["", "caf\u{E9}", "\u{20AC}100", "\u{10FFFF}\0x"] {
+            let encoded = encode(value).unwrap();
+            assert_eq!(encoded.len(), encoded_len(value));
+            assert_eq!(decode(&encoded).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        for bytes in [&[0x80][..], &[0xC3], &[0xC3, 0x41], &[0xE2, 0x82], &[0xE2, 0x41, 0x80], &[0xF0, 0x9F, 0x98, 0x80], &[0xFF]] {
+            assert!(matches!(decode(bytes), Err(BufferError::InvalidModifiedUtf8)), "{bytes:02X?}");
+        }
+        // Java allows unpaired surrogates, which decode lossily
+        assert_eq!(decode(&[0xED, 0xA0, 0xBD]).unwrap(), "\u{FFFD}");
+    }
+
+    #[test]
+    fn limits_the_encoded_length() {
+        assert!(encode(&"a".repeat(MAX_LENGTH)).is_ok());
+        assert!(matches!(encode(&"a".repeat(MAX_LENGTH + 1)), Err(BufferError::StringTooLong)));
+
+        // The limit counts encoded bytes, not characters
+        let value = format!("a{}", "\u{E9}".repeat(MAX_LENGTH / 2));
+        assert_eq!(encoded_len(&value), MAX_LENGTH);
+        assert!(encode(&value).is_ok());
+        assert!(matches!(encode(&format!("{value}\0")), Err(BufferError::StringTooLong)));
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt::{Display, Formatter};
+use crate::compound::FromTag;
+use crate::{NBTTag, TagContent};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// The path itself could not be parsed.
+    Syntax(String),
+    /// The compound has no entry with this key.
+    Missing,
+    /// The list index is past the end of the list.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The tag at this segment has a different type than the path or caller expects.
+    WrongType { expected: &'static str, found: &'static str },
+    /// A patch expected a different tag here than the one found.
+    Conflict,
+}
+
+/// A failed path query. `path` is the query up to and including the segment
+/// that failed, and is empty if the root itself has the wrong type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    pub path: String,
+    pub kind: PathErrorKind,
+}
+
+pub type PathResult<T> = Result<T, PathError>;
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            PathErrorKind::Syntax(message) => write!(f, "invalid path `{}`: {message}", self.path),
+            PathErrorKind::Missing => write!(f, "missing tag at `{}`", self.path),
+            PathErrorKind::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} out of bounds for list of length {len} at `{}`", self.path)
+            }
+            PathErrorKind::WrongType { expected, found } => {
+                write!(f, "expected {expected} at `{}`, found {found}", self.path)
+            }
+            PathErrorKind::Conflict => write!(f, "patch does not match the tag at `{}`", self.path),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// One step of a path into a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Appends a key to a path, quoting it if it would not parse back unquoted.
+pub(crate) fn push_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    if !key.is_empty() && !key.contains(['.', '[', ']', '"', '\\']) {
+        path.push_str(key);
+    } else {
+        path.push('"');
+        for c in key.chars() {
+            if c == '"' || c == '\\' {
+                path.push('\\');
+            }
+            path.push(c);
+        }
+        path.push('"');
+    }
+}
+
+/// Formats segments in the syntax accepted by [`TagContent::query`].
+pub(crate) fn format_path(segments: &[PathSegment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            PathSegment::Key(key) => push_key(&mut path, key),
+            PathSegment::Index(index) => path.push_str(&format!("[{index}]")),
+        }
+    }
+    path
+}
+
+enum Segment<'p> {
+    Key(String, &'p str),
+    Index(usize, &'p str),
+}
+
+/// Splits `a.b[2]."c.d"` into segments, each remembering the path prefix it ends.
+fn parse_path(path: &str) -> PathResult<Vec<Segment<'_>>> {
+    let syntax = |message: &str| PathError { path: path.to_string(), kind: PathErrorKind::Syntax(message.into()) };
+    let bytes = path.as_bytes();
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    let mut expect_key = true;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'[' => {
+                let end = path[pos..].find(']').ok_or_else(|| syntax("unclosed `[`"))? + pos;
+                let index = path[pos + 1..end].trim().parse().map_err(|_| syntax("list index must be a number"))?;
+                pos = end + 1;
+                segments.push(Segment::Index(index, &path[..pos]));
+                expect_key = false;
+            }
+            b'.' if !expect_key => {
+                pos += 1;
+                expect_key = true;
+                if pos == bytes.len() {
+                    return Err(syntax("path ends with `.`"));
+                }
+            }
+            b'"' if expect_key => {
+                let mut key = String::new();
+                let mut chars = path[pos + 1..].char_indices();
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, c)) => key.push(c),
+                            None => return Err(syntax("unterminated quoted key")),
+                        },
+                        Some((i, '"')) => break pos + 1 + i + 1,
+                        Some((_, c)) => key.push(c),
+                        None => return Err(syntax("unterminated quoted key")),
+                    }
+                };
+                pos = end;
+                segments.push(Segment::Key(key, &path[..pos]));
+                expect_key = false;
+            }
+            _ if expect_key => {
+                let len = path[pos..].find(['.', '[']).unwrap_or(path.len() - pos);
+                if len == 0 {
+                    return Err(syntax("empty key"));
+                }
+                segments.push(Segment::Key(path[pos..pos + len].to_string(), &path[..pos + len]));
+                pos += len;
+                expect_key = false;
+            }
+            _ => return Err(syntax("expected `.` or `[` between segments")),
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(syntax("path is empty"));
+    }
+    Ok(segments)
+}
+
+impl TagContent {
+    /// Looks up a nested tag by path, e.g. `sections[3].block_states.palette`.
+    /// Keys can be quoted (`"a.b"`) to include `.` or `[`.
+    pub fn query(&self, path: &str) -> PathResult<&TagContent> {
+        let mut current = self;
+        // Type errors name the segment holding the mistyped tag, not the one after it
+        let mut parent = "";
+        for segment in parse_path(path)? {
+            let prefix = match segment {
+                Segment::Key(key, prefix) => {
+                    current = match current {
+                        TagContent::Compound(compound) => compound.get(&key).ok_or_else(|| PathError {
+                            path: prefix.to_string(),
+                            kind: PathErrorKind::Missing,
+                        })?,
+                        other => return Err(wrong_type(parent, "compound", other)),
+                    };
+                    prefix
+                }
+                Segment::Index(index, prefix) => {
+                    current = match current {
+                        TagContent::List(items) => &items
+                            .get(index)
+                            .ok_or_else(|| PathError {
+                                path: prefix.to_string(),
+                                kind: PathErrorKind::IndexOutOfBounds { index, len: items.len() },
+                            })?
+                            .content,
+                        other => return Err(wrong_type(parent, "list", other)),
+                    };
+                    prefix
+                }
+            };
+            parent = prefix;
+        }
+        Ok(current)
+    }
+
+    /// Looks up a nested tag by path and converts it to `T`.
+    pub fn query_as<'a, T: FromTag<'a>>(&'a self, path: &str) -> PathResult<T> {
+        let content = self.query(path)?;
+        T::from_tag(content).ok_or_else(|| wrong_type(path, T::TYPE_NAME, content))
+    }
+}
+
+impl NBTTag {
+    /// See [`TagContent::query`].
+    pub fn query(&self, path: &str) -> PathResult<&TagContent> {
+        self.content.query(path)
+    }
+
+    /// See [`TagContent::query_as`].
+    pub fn query_as<'a, T: FromTag<'a>>(&'a self, path: &str) -> PathResult<T> {
+        self.content.query_as(path)
+    }
+}
+
+fn wrong_type(path: &str, expected: &'static str, found: &TagContent) -> PathError {
+    PathError {
+        path: path.to_string(),
+        kind: PathErrorKind::WrongType { expected, found: found.type_name() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NBTTag, NbtCompound, TagContent};
+    use super::{PathError, PathErrorKind};
+
+    fn chunk() -> NBTTag {
+        NBTTag::from_snbt(r#"{sections: [{y: -4b, block_states: {palette: ["air", "stone"]}}, {y: -3b}], "a.b": {c: 1}}"#).unwrap()
+    }
+
+    fn error(path: &str, kind: PathErrorKind) -> PathError {
+        PathError { path: path.into(), kind }
+    }
+
+    #[test]
+    fn queries_nested_keys_and_indices() {
+        let chunk = chunk();
+        assert_eq!(chunk.query_as::<i8>("sections[1].y"), Ok(-3));
+        assert_eq!(chunk.query_as::<&str>("sections[0].block_states.palette[1]"), Ok("stone"));
+        assert_eq!(chunk.query_as::<i32>(r#""a.b".c"#), Ok(1));
+        assert_eq!(chunk.query("sections[0]").map(TagContent::type_name), Ok("compound"));
+        assert!(chunk.query_as::<&NbtCompound>("sections[0].block_states").is_ok());
+    }
+
+    #[test]
+    fn reports_missing_paths() {
+        let chunk = chunk();
+        assert_eq!(chunk.query("sections[1].block_states.palette"), Err(error("sections[1].block_states", PathErrorKind::Missing)));
+        assert_eq!(
+            chunk.query("sections[2].y"),
+            Err(error("sections[2]", PathErrorKind::IndexOutOfBounds { index: 2, len: 2 })),
+        );
+    }
+
+    #[test]
+    fn reports_wrong_types() {
+        let chunk = chunk();
+        // Indexing a compound names the compound, not the index
+        assert_eq!(
+            chunk.query("sections[0].block_states[0]"),
+            Err(error("sections[0].block_states", PathErrorKind::WrongType { expected: "list", found: "compound" })),
+        );
+        assert_eq!(
+            chunk.query("sections.y"),
+            Err(error("sections", PathErrorKind::WrongType { expected: "compound", found: "list" })),
+        );
+        assert_eq!(
+            chunk.query_as::<i32>("sections[0].y"),
+            Err(error("sections[0].y", PathErrorKind::WrongType { expected: "int", found: "byte" })),
+        );
+    }
+
+    #[test]
+    fn rejects_bad_syntax() {
+        let chunk = chunk();
+        for path in ["", "a.", "sections[x]", "sections[0", "\"a", "a..b", "sections[0]y"] {
+            assert!(matches!(chunk.query(path), Err(PathError { kind: PathErrorKind::Syntax(_), .. })), "{path}");
+        }
+    }
+}
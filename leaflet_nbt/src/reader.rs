@@ -0,0 +1,195 @@
+use leaflet_network_buffer::{BufferError, BufferResult};
+use crate::limits::NbtAccounter;
+use crate::NbtFlavor;
+
+/// Cursor over encoded NBT, shared by the borrowed view and the visitor.
+#[derive(Clone)]
+pub(crate) struct Reader<'a> {
+    pub(crate) bytes: &'a [u8],
+    pub(crate) flavor: NbtFlavor,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8], flavor: NbtFlavor) -> Self {
+        Self { bytes, flavor }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> BufferResult<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(BufferError::BufferUnderflow);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> BufferResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn var_u64(&mut self, max_bits: u32) -> BufferResult<u64> {
+        let mut value = 0u64;
+        let mut position = 0;
+        loop {
+            if position >= max_bits {
+                return Err(BufferError::VarIntOverflow);
+            }
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as u64) << position;
+            position += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub(crate) fn u8(&mut self) -> BufferResult<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn byte(&mut self) -> BufferResult<i8> {
+        Ok(self.u8()? as i8)
+    }
+
+    pub(crate) fn short(&mut self) -> BufferResult<i16> {
+        Ok(match self.flavor {
+            NbtFlavor::Java => i16::from_be_bytes(self.array()?),
+            _ => i16::from_le_bytes(self.array()?),
+        })
+    }
+
+    pub(crate) fn int(&mut self) -> BufferResult<i32> {
+        Ok(match self.flavor {
+            NbtFlavor::Java => i32::from_be_bytes(self.array()?),
+            NbtFlavor::BedrockDisk => i32::from_le_bytes(self.array()?),
+            NbtFlavor::BedrockNetwork => {
+                let value = self.var_u64(35)? as u32;
+                ((value >> 1) as i32) ^ -((value & 1) as i32)
+            }
+        })
+    }
+
+    pub(crate) fn long(&mut self) -> BufferResult<i64> {
+        Ok(match self.flavor {
+            NbtFlavor::Java => i64::from_be_bytes(self.array()?),
+            NbtFlavor::BedrockDisk => i64::from_le_bytes(self.array()?),
+            NbtFlavor::BedrockNetwork => {
+                let value = self.var_u64(70)?;
+                ((value >> 1) as i64) ^ -((value & 1) as i64)
+            }
+        })
+    }
+
+    pub(crate) fn float(&mut self) -> BufferResult<f32> {
+        Ok(match self.flavor {
+            NbtFlavor::Java => f32::from_be_bytes(self.array()?),
+            _ => f32::from_le_bytes(self.array()?),
+        })
+    }
+
+    pub(crate) fn double(&mut self) -> BufferResult<f64> {
+        Ok(match self.flavor {
+            NbtFlavor::Java => f64::from_be_bytes(self.array()?),
+            _ => f64::from_le_bytes(self.array()?),
+        })
+    }
+
+    /// Reads an array or list length, rejecting negative values.
+    pub(crate) fn length(&mut self) -> BufferResult<usize> {
+        usize::try_from(self.int()?).map_err(|_| BufferError::NegativeLength)
+    }
+
+    /// Reads a length-prefixed string, returning its encoded bytes.
+    pub(crate) fn string(&mut self, accounter: &NbtAccounter) -> BufferResult<&'a [u8]> {
+        let len = match self.flavor {
+            NbtFlavor::Java => u16::from_be_bytes(self.array()?) as usize,
+            NbtFlavor::BedrockDisk => u16::from_le_bytes(self.array()?) as usize,
+            NbtFlavor::BedrockNetwork => self.var_u64(35)? as u32 as usize,
+        };
+        accounter.reserve_bytes(self.bytes.len(), len)?;
+        self.take(len)
+    }
+}
+
+/// Moves the reader past a tag's content, checking its structure and the limits.
+pub(crate) fn skip(type_id: u8, reader: &mut Reader, accounter: &mut NbtAccounter) -> BufferResult<()> {
+    if let Some(size) = reader.flavor.fixed_size(type_id) {
+        return reader.take(size).map(|_| ());
+    }
+    match type_id {
+        3 => {
+            reader.int()?;
+        }
+        4 => {
+            reader.long()?;
+        }
+        7 | 11 | 12 => {
+            let element_type = match type_id {
+                7 => 1,
+                11 => 3,
+                _ => 4,
+            };
+            let len = reader.length()?;
+            match reader.flavor.fixed_size(element_type) {
+                Some(size) => {
+                    let size = len.saturating_mul(size);
+                    accounter.reserve_bytes(reader.bytes.len(), size)?;
+                    reader.take(size)?;
+                }
+                None => {
+                    accounter.reserve_bytes(reader.bytes.len(), len)?;
+                    for _ in 0..len {
+                        skip(element_type, reader, accounter)?;
+                    }
+                }
+            }
+        }
+        8 => {
+            reader.string(accounter)?;
+        }
+        9 => {
+            let type_id = reader.u8()?;
+            let len = reader.length()?;
+            if type_id == 0 && len > 0 {
+                return Err(BufferError::Generic("List of end tags"));
+            }
+            accounter.count_elements(reader.bytes.len(), len)?;
+            accounter.push_depth()?;
+            skip_elements(type_id, len, reader, accounter)?;
+            accounter.pop_depth();
+        }
+        10 => {
+            accounter.push_depth()?;
+            loop {
+                let type_id = reader.u8()?;
+                if type_id == 0 {
+                    break
+                }
+
+                accounter.count_elements(reader.bytes.len(), 1)?;
+                reader.string(accounter)?;
+                skip(type_id, reader, accounter)?;
+            }
+            accounter.pop_depth();
+        }
+        _ => return Err(BufferError::Generic("Unknown tag type")),
+    }
+    Ok(())
+}
+
+/// Moves the reader past the elements of a list whose header was already read and counted.
+pub(crate) fn skip_elements(type_id: u8, len: usize, reader: &mut Reader, accounter: &mut NbtAccounter) -> BufferResult<()> {
+    match reader.flavor.fixed_size(type_id) {
+        Some(size) => {
+            let size = len.saturating_mul(size);
+            accounter.reserve_bytes(reader.bytes.len(), size)?;
+            reader.take(size)?;
+        }
+        None => {
+            for _ in 0..len {
+                skip(type_id, reader, accounter)?;
+            }
+        }
+    }
+    Ok(())
+}
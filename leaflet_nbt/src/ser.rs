@@ -0,0 +1,702 @@
+use serde::ser::{
+    Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+    SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Serialize, Serializer};
+use leaflet_network_buffer::McBuf;
+use crate::error::{SerdeError, SerdeResult};
+use crate::{NBTTag, NbtCompound, TagContent};
+
+// Newtype names used to smuggle typed arrays through the serde data model.
+pub(crate) const BYTE_ARRAY_TOKEN: &str = "__leaflet_nbt_byte_array";
+pub(crate) const INT_ARRAY_TOKEN: &str = "__leaflet_nbt_int_array";
+pub(crate) const LONG_ARRAY_TOKEN: &str = "__leaflet_nbt_long_array";
+pub(crate) const TAG_TOKEN: &str = "__leaflet_nbt_tag";
+
+/// Serializes a value into a nameless root tag.
+pub fn to_nbt<T: Serialize + ?Sized>(value: &T) -> SerdeResult<NBTTag> {
+    match value.serialize(NbtSerializer)? {
+        TagContent::End => Err(SerdeError::EmptyRoot),
+        content => Ok(NBTTag { content, name: None }),
+    }
+}
+
+/// Serializes a value and writes it to the buffer as network NBT.
+pub fn to_buf<T: Serialize + ?Sized>(value: &T, buf: &mut McBuf) -> SerdeResult<()> {
+    let tag = to_nbt(value)?;
+    tag.try_write(buf)?;
+    Ok(())
+}
+
+/// Serializer producing the content of a tag. `None` and `()` serialize to
+/// [`TagContent::End`], which compounds skip and lists reject.
+pub struct NbtSerializer;
+
+fn string_key(key: TagContent) -> SerdeResult<String> {
+    match key {
+        TagContent::String(key) => Ok(key),
+        _ => Err(SerdeError::KeyMustBeString),
+    }
+}
+
+impl Serializer for NbtSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = VariantSerializer<ListSerializer>;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = VariantSerializer<CompoundSerializer>;
+
+    fn serialize_bool(self, v: bool) -> SerdeResult<TagContent> {
+        Ok(TagContent::Byte(v as i8))
+    }
+
+    fn serialize_i8(self, v: i8) -> SerdeResult<TagContent> {
+        Ok(TagContent::Byte(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> SerdeResult<TagContent> {
+        Ok(TagContent::Short(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> SerdeResult<TagContent> {
+        Ok(TagContent::Int(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> SerdeResult<TagContent> {
+        Ok(TagContent::Long(v))
+    }
+
+    // Unsigned integers are stored bit-for-bit in the signed tag of the same width
+    fn serialize_u8(self, v: u8) -> SerdeResult<TagContent> {
+        Ok(TagContent::Byte(v as i8))
+    }
+
+    fn serialize_u16(self, v: u16) -> SerdeResult<TagContent> {
+        Ok(TagContent::Short(v as i16))
+    }
+
+    fn serialize_u32(self, v: u32) -> SerdeResult<TagContent> {
+        Ok(TagContent::Int(v as i32))
+    }
+
+    fn serialize_u64(self, v: u64) -> SerdeResult<TagContent> {
+        Ok(TagContent::Long(v as i64))
+    }
+
+    fn serialize_f32(self, v: f32) -> SerdeResult<TagContent> {
+        Ok(TagContent::Float(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> SerdeResult<TagContent> {
+        Ok(TagContent::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> SerdeResult<TagContent> {
+        Ok(TagContent::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> SerdeResult<TagContent> {
+        Ok(TagContent::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> SerdeResult<TagContent> {
+        Ok(TagContent::ByteArray(v.iter().map(|b| *b as i8).collect()))
+    }
+
+    fn serialize_none(self) -> SerdeResult<TagContent> {
+        Ok(TagContent::End)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> SerdeResult<TagContent> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> SerdeResult<TagContent> {
+        Ok(TagContent::End)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SerdeResult<TagContent> {
+        Ok(TagContent::Compound(NbtCompound::new()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> SerdeResult<TagContent> {
+        Ok(TagContent::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> SerdeResult<TagContent> {
+        let content = value.serialize(self)?;
+        match name {
+            BYTE_ARRAY_TOKEN => into_array(content, "byte array", |c| match c {
+                TagContent::Byte(v) => Some(v),
+                _ => None,
+            })
+            .map(TagContent::ByteArray),
+            INT_ARRAY_TOKEN => into_array(content, "int array", |c| match c {
+                TagContent::Int(v) => Some(v),
+                _ => None,
+            })
+            .map(TagContent::IntArray),
+            LONG_ARRAY_TOKEN => into_array(content, "long array", |c| match c {
+                TagContent::Long(v) => Some(v),
+                _ => None,
+            })
+            .map(TagContent::LongArray),
+            _ => Ok(content),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> SerdeResult<TagContent> {
+        let mut compound = CompoundSerializer::default();
+        compound.push(variant.to_string(), value.serialize(NbtSerializer)?);
+        compound.end()
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> SerdeResult<ListSerializer> {
+        Ok(ListSerializer { items: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> SerdeResult<ListSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> SerdeResult<ListSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> SerdeResult<VariantSerializer<ListSerializer>> {
+        Ok(VariantSerializer { variant, inner: self.serialize_seq(Some(len))? })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> SerdeResult<CompoundSerializer> {
+        Ok(CompoundSerializer::default())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> SerdeResult<CompoundSerializer> {
+        Ok(CompoundSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> SerdeResult<VariantSerializer<CompoundSerializer>> {
+        Ok(VariantSerializer { variant, inner: CompoundSerializer::default() })
+    }
+}
+
+fn into_array<T>(
+    content: TagContent,
+    kind: &'static str,
+    unwrap: impl Fn(TagContent) -> Option<T>,
+) -> SerdeResult<Vec<T>> {
+    match content {
+        TagContent::List(items) => items
+            .into_iter()
+            .map(|item| unwrap(item.content).ok_or(SerdeError::InvalidArray(kind)))
+            .collect(),
+        _ => Err(SerdeError::InvalidArray(kind)),
+    }
+}
+
+pub struct ListSerializer {
+    items: Vec<NBTTag>,
+}
+
+impl ListSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        let content = value.serialize(NbtSerializer)?;
+        if let TagContent::End = content {
+            return Err(SerdeError::NoneInList);
+        }
+        if let Some(first) = self.items.first() {
+            let expected = first.content.type_id();
+            if content.type_id() != expected {
+                return Err(SerdeError::ListTypeMismatch { expected, found: content.type_id() });
+            }
+        }
+        self.items.push(NBTTag { content, name: None });
+        Ok(())
+    }
+}
+
+impl SerializeSeq for ListSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        Ok(TagContent::List(self.items))
+    }
+}
+
+impl SerializeTuple for ListSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for ListSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        SerializeSeq::end(self)
+    }
+}
+
+#[derive(Default)]
+pub struct CompoundSerializer {
+    entries: NbtCompound,
+    next_key: Option<String>,
+}
+
+impl CompoundSerializer {
+    fn push(&mut self, key: String, content: TagContent) {
+        if let TagContent::End = content {
+            return;
+        }
+        self.entries.insert(key, content);
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        Ok(TagContent::Compound(self.entries))
+    }
+}
+
+impl SerializeMap for CompoundSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> SerdeResult<()> {
+        self.next_key = Some(string_key(key.serialize(KeySerializer)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        let key = self
+            .next_key
+            .take()
+            .ok_or(SerdeError::Custom("serialize_value called before serialize_key".into()))?;
+        let content = value.serialize(NbtSerializer)?;
+        self.push(key, content);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        CompoundSerializer::end(self)
+    }
+}
+
+impl SerializeStruct for CompoundSerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> SerdeResult<()> {
+        let content = value.serialize(NbtSerializer)?;
+        self.push(key.to_string(), content);
+        Ok(())
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        CompoundSerializer::end(self)
+    }
+}
+
+/// Wraps the content of a tuple or struct variant in a single-entry compound
+/// keyed by the variant name.
+pub struct VariantSerializer<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn wrap(variant: &'static str, content: TagContent) -> SerdeResult<TagContent> {
+        let mut compound = CompoundSerializer::default();
+        compound.push(variant.to_string(), content);
+        compound.end()
+    }
+}
+
+impl SerializeTupleVariant for VariantSerializer<ListSerializer> {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> SerdeResult<()> {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        Self::wrap(self.variant, SerializeSeq::end(self.inner)?)
+    }
+}
+
+impl SerializeStructVariant for VariantSerializer<CompoundSerializer> {
+    type Ok = TagContent;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> SerdeResult<()> {
+        SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> SerdeResult<TagContent> {
+        Self::wrap(self.variant, self.inner.end()?)
+    }
+}
+
+/// Only accepts values that can be used as compound keys.
+struct KeySerializer;
+
+impl Serializer for KeySerializer {
+    type Ok = TagContent;
+    type Error = SerdeError;
+    type SerializeSeq = Impossible<TagContent, SerdeError>;
+    type SerializeTuple = Impossible<TagContent, SerdeError>;
+    type SerializeTupleStruct = Impossible<TagContent, SerdeError>;
+    type SerializeTupleVariant = Impossible<TagContent, SerdeError>;
+    type SerializeMap = Impossible<TagContent, SerdeError>;
+    type SerializeStruct = Impossible<TagContent, SerdeError>;
+    type SerializeStructVariant = Impossible<TagContent, SerdeError>;
+
+    fn serialize_bool(self, _v: bool) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_i8(self, _v: i8) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_i16(self, _v: i16) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_i32(self, _v: i32) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_i64(self, _v: i64) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_u8(self, _v: u8) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_u16(self, _v: u16) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_u32(self, _v: u32) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_u64(self, _v: u64) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_f32(self, _v: f32) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_f64(self, _v: f64) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_char(self, v: char) -> SerdeResult<TagContent> {
+        NbtSerializer.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> SerdeResult<TagContent> {
+        NbtSerializer.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_none(self) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_unit(self) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> SerdeResult<TagContent> {
+        NbtSerializer.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> SerdeResult<TagContent> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> SerdeResult<TagContent> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> SerdeResult<Self::SerializeSeq> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> SerdeResult<Self::SerializeTuple> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> SerdeResult<Self::SerializeTupleStruct> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> SerdeResult<Self::SerializeTupleVariant> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> SerdeResult<Self::SerializeMap> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> SerdeResult<Self::SerializeStruct> {
+        Err(SerdeError::KeyMustBeString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> SerdeResult<Self::SerializeStructVariant> {
+        Err(SerdeError::KeyMustBeString)
+    }
+}
+
+impl Serialize for TagContent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TagContent::End => serializer.serialize_unit(),
+            TagContent::Byte(v) => serializer.serialize_i8(*v),
+            TagContent::Short(v) => serializer.serialize_i16(*v),
+            TagContent::Int(v) => serializer.serialize_i32(*v),
+            TagContent::Long(v) => serializer.serialize_i64(*v),
+            TagContent::Float(v) => serializer.serialize_f32(*v),
+            TagContent::Double(v) => serializer.serialize_f64(*v),
+            TagContent::ByteArray(v) => serializer.serialize_newtype_struct(BYTE_ARRAY_TOKEN, v),
+            TagContent::String(v) => serializer.serialize_str(v),
+            TagContent::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(&item.content)?;
+                }
+                seq.end()
+            }
+            TagContent::Compound(entries) => entries.serialize(serializer),
+            TagContent::IntArray(v) => serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, v),
+            TagContent::LongArray(v) => serializer.serialize_newtype_struct(LONG_ARRAY_TOKEN, v),
+        }
+    }
+}
+
+impl Serialize for NBTTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.content.serialize(serializer)
+    }
+}
+
+impl Serialize for NbtCompound {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (name, content) in self.iter() {
+            map.serialize_entry(name, content)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
+    use leaflet_network_buffer::McBuf;
+    use crate::{array, from_buf, from_nbt, to_buf, to_nbt, SerdeError, TagContent};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Point(i32, i32),
+        Rect { width: i16, height: i16 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Everything {
+        flag: bool,
+        byte: i8,
+        long: i64,
+        double: f64,
+        name: String,
+        missing: Option<String>,
+        present: Option<u8>,
+        empty: Shape,
+        shapes: Vec<Shape>,
+        nested: Vec<Vec<i32>>,
+        map: BTreeMap<String, i16>,
+        #[serde(with = "array::byte_array")]
+        bytes: Vec<i8>,
+        #[serde(with = "array::int_array")]
+        ints: Vec<i32>,
+        #[serde(with = "array::long_array")]
+        longs: Vec<i64>,
+    }
+
+    fn everything() -> Everything {
+        Everything {
+            flag: true,
+            byte: -5,
+            long: i64::MIN,
+            double: 0.25,
+            name: "leaflet".into(),
+            missing: None,
+            present: Some(7),
+            empty: Shape::Empty,
+            shapes: vec![
+                Shape::Circle(1.5),
+                Shape::Point(1, -1),
+                Shape::Rect { width: 2, height: 3 },
+            ],
+            nested: vec![vec![1, 2], vec![]],
+            map: BTreeMap::from([("a".into(), 1), ("b".into(), 2)]),
+            bytes: vec![1, -2, 3],
+            ints: vec![i32::MAX, 0],
+            longs: vec![-1, 1 << 40],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_tags() {
+        let value = everything();
+        let tag = to_nbt(&value).unwrap();
+        assert_eq!(from_nbt::<Everything>(&tag).unwrap(), value);
+    }
+
+    #[test]
+    fn round_trips_through_buffers() {
+        let value = everything();
+        let mut buf = McBuf::new();
+        to_buf(&value, &mut buf).unwrap();
+        assert_eq!(from_buf::<Everything>(&mut buf).unwrap(), value);
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn encodes_arrays_and_options() {
+        let tag = to_nbt(&everything()).unwrap();
+        let compound = tag.content.as_compound().unwrap();
+        assert!(matches!(compound.get("bytes"), Some(TagContent::ByteArray(_))));
+        assert!(matches!(compound.get("ints"), Some(TagContent::IntArray(_))));
+        assert!(matches!(compound.get("longs"), Some(TagContent::LongArray(_))));
+        assert!(matches!(compound.get("nested"), Some(TagContent::List(_))));
+        assert!(matches!(compound.get("flag"), Some(TagContent::Byte(1))));
+        assert!(matches!(compound.get("empty"), Some(TagContent::String(_))));
+        assert!(!compound.contains_key("missing"));
+    }
+
+    #[test]
+    fn rejects_unrepresentable_values() {
+        assert!(matches!(to_nbt(&()), Err(SerdeError::EmptyRoot)));
+        assert!(matches!(to_nbt(&vec![None, Some(1)]), Err(SerdeError::NoneInList)));
+        assert!(to_nbt(&vec![Shape::Empty, Shape::Circle(1.0)]).is_err());
+        assert!(to_nbt(&BTreeMap::from([(1, 2)])).is_err());
+    }
+}
@@ -0,0 +1,578 @@
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+use crate::{NBTTag, NbtCompound, NbtLimits, TagContent};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnbtError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for SnbtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for SnbtError {}
+
+pub type SnbtResult<T> = Result<T, SnbtError>;
+
+impl NBTTag {
+    /// Parses a stringified NBT value into a nameless tag.
+    pub fn from_snbt(input: &str) -> SnbtResult<NBTTag> {
+        let mut parser = SnbtParser { input, pos: 0, depth: 0 };
+        let content = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos != input.len() {
+            return Err(parser.error("Trailing characters after value"));
+        }
+        Ok(NBTTag { content, name: None })
+    }
+
+    /// Prints the tag content as SNBT without any whitespace. The name of the
+    /// tag itself is not part of the output.
+    pub fn to_snbt(&self) -> String {
+        self.content.to_snbt()
+    }
+
+    /// Prints the tag content as SNBT, indenting nested compounds and lists.
+    pub fn to_snbt_pretty(&self) -> String {
+        let mut out = String::new();
+        write_content(&mut out, &self.content, Some("    "), 0);
+        out
+    }
+}
+
+impl TagContent {
+    /// Prints the content as SNBT without any whitespace.
+    pub fn to_snbt(&self) -> String {
+        let mut out = String::new();
+        write_content(&mut out, self, None, 0);
+        out
+    }
+}
+
+impl Display for NBTTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_snbt())
+    }
+}
+
+impl FromStr for NBTTag {
+    type Err = SnbtError;
+
+    fn from_str(s: &str) -> SnbtResult<Self> {
+        NBTTag::from_snbt(s)
+    }
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+struct SnbtParser<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Lists and compounds currently open, bounded like network NBT.
+    depth: usize,
+}
+
+impl<'a> SnbtParser<'a> {
+    fn error(&self, message: impl Into<String>) -> SnbtError {
+        SnbtError { position: self.pos, message: message.into() }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> SnbtResult<()> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => {
+                self.pos -= c.len_utf8();
+                Err(self.error(format!("Expected '{expected}', found '{c}'")))
+            }
+            None => Err(self.error(format!("Expected '{expected}', found end of input"))),
+        }
+    }
+
+    /// Consumes the separator between elements, returning false once the
+    /// closing character has been consumed instead.
+    fn separator(&mut self, close: char) -> SnbtResult<bool> {
+        self.skip_whitespace();
+        match self.next() {
+            Some(',') => Ok(true),
+            Some(c) if c == close => Ok(false),
+            Some(c) => {
+                self.pos -= c.len_utf8();
+                Err(self.error(format!("Expected ',' or '{close}', found '{c}'")))
+            }
+            None => Err(self.error(format!("Expected ',' or '{close}', found end of input"))),
+        }
+    }
+
+    fn parse_value(&mut self) -> SnbtResult<TagContent> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_nested(Self::parse_compound),
+            Some('[') => self.parse_nested(Self::parse_list_or_array),
+            Some('"') | Some('\'') => self.parse_quoted().map(TagContent::String),
+            Some(_) => {
+                let start = self.pos;
+                let token = self.parse_unquoted()?;
+                parse_primitive(token).map_err(|message| SnbtError { position: start, message })
+            }
+            None => Err(self.error("Expected value, found end of input")),
+        }
+    }
+
+    fn parse_nested(&mut self, parse: impl FnOnce(&mut Self) -> SnbtResult<TagContent>) -> SnbtResult<TagContent> {
+        if self.depth >= NbtLimits::NETWORK.max_depth {
+            return Err(self.error("Too deeply nested"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_key(&mut self) -> SnbtResult<String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') | Some('\'') => self.parse_quoted(),
+            _ => self.parse_unquoted().map(str::to_string),
+        }
+    }
+
+    fn parse_unquoted(&mut self) -> SnbtResult<&'a str> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return match self.peek() {
+                Some(c) => Err(self.error(format!("Unexpected character '{c}'"))),
+                None => Err(self.error("Unexpected end of input")),
+            };
+        }
+        Ok(&self.input[start..self.pos])
+    }
+
+    fn parse_quoted(&mut self) -> SnbtResult<String> {
+        let quote = self.next().ok_or_else(|| self.error("Expected string"))?;
+        let mut result = String::new();
+        loop {
+            match self.next() {
+                Some('\\') => {
+                    let escape_pos = self.pos - 1;
+                    match self.next() {
+                        Some(c) if c == '\\' || c == '"' || c == '\'' => result.push(c),
+                        Some('n') => result.push('\n'),
+                        Some('t') => result.push('\t'),
+                        Some('r') => result.push('\r'),
+                        Some('b') => result.push('\u{8}'),
+                        Some('f') => result.push('\u{c}'),
+                        Some('s') => result.push(' '),
+                        Some('u') => {
+                            let hex = self.input.get(self.pos..self.pos + 4).unwrap_or("");
+                            let value = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+                            match value {
+                                Some(c) if hex.len() == 4 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                                    result.push(c);
+                                    self.pos += 4;
+                                }
+                                _ => {
+                                    return Err(SnbtError {
+                                        position: escape_pos,
+                                        message: "Invalid unicode escape".into(),
+                                    });
+                                }
+                            }
+                        }
+                        Some(c) => {
+                            return Err(SnbtError {
+                                position: escape_pos,
+                                message: format!("Invalid escape sequence '\\{c}'"),
+                            });
+                        }
+                        None => return Err(self.error("Unterminated string")),
+                    }
+                }
+                Some(c) if c == quote => return Ok(result),
+                Some(c) => result.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+    }
+
+    fn parse_compound(&mut self) -> SnbtResult<TagContent> {
+        self.expect('{')?;
+        let mut entries = NbtCompound::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(TagContent::Compound(entries));
+        }
+        loop {
+            let key_pos = self.pos;
+            let key = self.parse_key()?;
+            if entries.contains_key(&key) {
+                return Err(SnbtError { position: key_pos, message: format!("Duplicate key '{key}'") });
+            }
+            self.expect(':')?;
+            let content = self.parse_value()?;
+            entries.insert(key, content);
+            if !self.separator('}')? {
+                return Ok(TagContent::Compound(entries));
+            }
+        }
+    }
+
+    fn parse_list_or_array(&mut self) -> SnbtResult<TagContent> {
+        self.expect('[')?;
+        let mut rest = self.input[self.pos..].chars();
+        let array_type = rest.next().filter(|_| rest.next() == Some(';'));
+        match array_type {
+            Some('B') => {
+                self.pos += 2;
+                self.parse_array(|c| match c {
+                    TagContent::Byte(v) => Some(v),
+                    TagContent::Int(v) => i8::try_from(v).ok(),
+                    _ => None,
+                })
+                .map(TagContent::ByteArray)
+            }
+            Some('I') => {
+                self.pos += 2;
+                self.parse_array(|c| match c {
+                    TagContent::Int(v) => Some(v),
+                    _ => None,
+                })
+                .map(TagContent::IntArray)
+            }
+            Some('L') => {
+                self.pos += 2;
+                self.parse_array(|c| match c {
+                    TagContent::Long(v) => Some(v),
+                    TagContent::Int(v) => Some(v as i64),
+                    _ => None,
+                })
+                .map(TagContent::LongArray)
+            }
+            Some(c) => Err(self.error(format!("Invalid array type '{c}'"))),
+            None => self.parse_list(),
+        }
+    }
+
+    fn parse_array<T>(&mut self, unwrap: impl Fn(TagContent) -> Option<T>) -> SnbtResult<Vec<T>> {
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(values);
+        }
+        loop {
+            self.skip_whitespace();
+            let value_pos = self.pos;
+            let value = self.parse_value()?;
+            match unwrap(value) {
+                Some(value) => values.push(value),
+                None => {
+                    return Err(SnbtError {
+                        position: value_pos,
+                        message: "Array element has the wrong type".into(),
+                    });
+                }
+            }
+            if !self.separator(']')? {
+                return Ok(values);
+            }
+        }
+    }
+
+    fn parse_list(&mut self) -> SnbtResult<TagContent> {
+        let mut items: Vec<NBTTag> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(TagContent::List(items));
+        }
+        loop {
+            self.skip_whitespace();
+            let value_pos = self.pos;
+            let content = self.parse_value()?;
+            if let Some(first) = items.first()
+                && first.content.type_id() != content.type_id()
+            {
+                return Err(SnbtError {
+                    position: value_pos,
+                    message: "List elements must all have the same type".into(),
+                });
+            }
+            items.push(NBTTag { content, name: None });
+            if !self.separator(']')? {
+                return Ok(TagContent::List(items));
+            }
+        }
+    }
+}
+
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_decimal(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    (!int_part.is_empty() || !frac_part.is_empty())
+        && digits(int_part)
+        && digits(frac_part)
+        && exponent.is_none_or(is_integer)
+}
+
+fn is_special_float(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    ["nan", "inf", "infinity"].iter().any(|v| s.eq_ignore_ascii_case(v))
+}
+
+fn parse_primitive(token: &str) -> Result<TagContent, String> {
+    let out_of_range = || format!("Number '{token}' is out of range");
+
+    if token == "true" {
+        return Ok(TagContent::Byte(1));
+    }
+    if token == "false" {
+        return Ok(TagContent::Byte(0));
+    }
+
+    let (body, suffix) = token.split_at(token.len() - 1);
+    match suffix {
+        "b" | "B" if is_integer(body) => {
+            return body.parse().map(TagContent::Byte).map_err(|_| out_of_range());
+        }
+        "s" | "S" if is_integer(body) => {
+            return body.parse().map(TagContent::Short).map_err(|_| out_of_range());
+        }
+        "l" | "L" if is_integer(body) => {
+            return body.parse().map(TagContent::Long).map_err(|_| out_of_range());
+        }
+        "f" | "F" if is_decimal(body) || is_special_float(body) => {
+            return body.parse().map(TagContent::Float).map_err(|_| out_of_range());
+        }
+        "d" | "D" if is_decimal(body) || is_special_float(body) => {
+            return body.parse().map(TagContent::Double).map_err(|_| out_of_range());
+        }
+        _ => {}
+    }
+
+    if is_integer(token) {
+        return token.parse().map(TagContent::Int).map_err(|_| out_of_range());
+    }
+    if is_decimal(token) {
+        return token.parse().map(TagContent::Double).map_err(|_| out_of_range());
+    }
+
+    Ok(TagContent::String(token.to_string()))
+}
+
+fn write_quoted(out: &mut String, value: &str) {
+    let quote = if value.contains('"') && !value.contains('\'') { '\'' } else { '"' };
+    out.push(quote);
+    for c in value.chars() {
+        if c == '\\' || c == quote {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push(quote);
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if !key.is_empty() && key.chars().all(is_unquoted_char) {
+        out.push_str(key);
+    } else {
+        write_quoted(out, key);
+    }
+}
+
+fn write_newline(out: &mut String, indent: Option<&str>, depth: usize) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str(indent);
+        }
+    }
+}
+
+fn write_array<T: Copy>(out: &mut String, prefix: char, values: &[T], indent: Option<&str>, write: impl Fn(&mut String, T)) {
+    out.push('[');
+    out.push(prefix);
+    out.push(';');
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if indent.is_some() {
+            out.push(' ');
+        }
+        write(out, *value);
+    }
+    out.push(']');
+}
+
+fn write_content(out: &mut String, content: &TagContent, indent: Option<&str>, depth: usize) {
+    // Writing into a String cannot fail
+    match content {
+        TagContent::End => {}
+        TagContent::Byte(v) => write!(out, "{v}b").unwrap(),
+        TagContent::Short(v) => write!(out, "{v}s").unwrap(),
+        TagContent::Int(v) => write!(out, "{v}").unwrap(),
+        TagContent::Long(v) => write!(out, "{v}L").unwrap(),
+        TagContent::Float(v) => write!(out, "{v:?}f").unwrap(),
+        TagContent::Double(v) => write!(out, "{v:?}d").unwrap(),
+        TagContent::ByteArray(v) => write_array(out, 'B', v, indent, |out, v| write!(out, "{v}b").unwrap()),
+        TagContent::String(v) => write_quoted(out, v),
+        TagContent::List(items) => {
+            // Lists of primitives stay on a single line when pretty printing
+            let nested = items
+                .iter()
+                .any(|i| matches!(i.content, TagContent::List(_) | TagContent::Compound(_)));
+            let item_indent = indent.filter(|_| nested);
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                    if indent.is_some() && !nested {
+                        out.push(' ');
+                    }
+                }
+                write_newline(out, item_indent, depth + 1);
+                write_content(out, &item.content, indent, depth + 1);
+            }
+            if !items.is_empty() {
+                write_newline(out, item_indent, depth);
+            }
+            out.push(']');
+        }
+        TagContent::Compound(entries) => {
+            out.push('{');
+            for (i, (name, content)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, depth + 1);
+                write_key(out, name);
+                out.push(':');
+                if indent.is_some() {
+                    out.push(' ');
+                }
+                write_content(out, content, indent, depth + 1);
+            }
+            if !entries.is_empty() {
+                write_newline(out, indent, depth);
+            }
+            out.push('}');
+        }
+        TagContent::IntArray(v) => write_array(out, 'I', v, indent, |out, v| write!(out, "{v}").unwrap()),
+        TagContent::LongArray(v) => write_array(out, 'L', v, indent, |out, v| write!(out, "{v}L").unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use leaflet_network_buffer::McBuf;
+    use crate::{NBTTag, TagContent};
+
+    /// Parses `snbt`, sends it through the binary format and prints it again.
+    fn round_trip(snbt: &str) -> String {
+        let tag = NBTTag::from_snbt(snbt).unwrap();
+        let mut buf = McBuf::new();
+        tag.content.write_id(&mut buf);
+        tag.content.write_content(&mut buf);
+        let type_id = buf.read_u8().unwrap();
+        let content = TagContent::read_content(type_id, &mut buf).unwrap();
+        assert_eq!(buf.remaining(), 0);
+        content.to_snbt()
+    }
+
+    #[test]
+    fn typed_suffixes() {
+        assert_eq!(round_trip("{a:1b,b:2s,c:3,d:4L,e:1.5f,f:2.5d,g:true,h:7.0}"), "{a:1b,b:2s,c:3,d:4L,e:1.5f,f:2.5d,g:1b,h:7.0d}");
+        assert_eq!(round_trip("[-128B,127b]"), "[-128b,127b]");
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(round_trip("[B;1b,-2b]"), "[B;1b,-2b]");
+        assert_eq!(round_trip("[I; 1, 2, 3]"), "[I;1,2,3]");
+        assert_eq!(round_trip("[L;1L,-9223372036854775808L]"), "[L;1L,-9223372036854775808L]");
+        assert_eq!(round_trip("[I;]"), "[I;]");
+        assert!(NBTTag::from_snbt("[I;1,2L]").is_err());
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(round_trip(r#"{"with space":1,'quote"d':2,"":3,plain:4}"#), r#"{"with space":1,'quote"d':2,"":3,plain:4}"#);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(round_trip(r#""\u00e9\u2603""#), r#""é☃""#);
+        assert_eq!(round_trip(r#"'it\'s'"#), r#""it's""#);
+        assert!(NBTTag::from_snbt(r#""\u12""#).is_err());
+        assert!(NBTTag::from_snbt(r#""\q""#).is_err());
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(round_trip("[1e3d,-0.0d,.5d,1.e-2d]"), "[1000.0d,-0.0d,0.5d,0.01d]");
+        assert_eq!(round_trip("[NaNf,Infinityf,-inff]"), "[NaNf,inff,-inff]");
+        assert_eq!(round_trip("3.4028235e38f"), "3.4028235e38f");
+    }
+
+    #[test]
+    fn rejects_heterogeneous_lists() {
+        assert!(NBTTag::from_snbt("[1,2b]").is_err());
+        assert!(NBTTag::from_snbt("[{},[]]").is_err());
+        assert_eq!(round_trip("[[1],[a]]"), r#"[[1],["a"]]"#);
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        assert!(NBTTag::from_snbt(&"[".repeat(200_000)).is_err());
+        let deep = format!("{}{}", "[".repeat(512), "]".repeat(512));
+        assert!(NBTTag::from_snbt(&deep).is_ok());
+        let too_deep = format!("{}{}", "[".repeat(513), "]".repeat(513));
+        assert!(NBTTag::from_snbt(&too_deep).is_err());
+    }
+}
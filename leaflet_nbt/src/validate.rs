@@ -0,0 +1,190 @@
+use leaflet_network_buffer::McBuf;
+use crate::error::{TagError, TagErrorKind, TagResult};
+use crate::path::push_key;
+use crate::{NBTTag, NbtFlavor, TagContent};
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// An error on its way up the tree, collecting the path in reverse.
+struct Invalid<'a> {
+    kind: TagErrorKind,
+    segments: Vec<Segment<'a>>,
+}
+
+impl Invalid<'_> {
+    fn new(kind: TagErrorKind) -> Self {
+        Self { kind, segments: Vec::new() }
+    }
+
+    fn into_error(self) -> TagError {
+        let mut path = String::new();
+        for segment in self.segments.iter().rev() {
+            match segment {
+                Segment::Key(key) => push_key(&mut path, key),
+                Segment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+        TagError { path, kind: self.kind }
+    }
+}
+
+fn check_string(value: &str, flavor: NbtFlavor) -> Result<(), Invalid<'_>> {
+    let len = flavor.string_len(value);
+    if len > flavor.max_string_len() {
+        return Err(Invalid::new(TagErrorKind::StringTooLong(len)));
+    }
+    Ok(())
+}
+
+fn check_len<'a>(len: usize) -> Result<(), Invalid<'a>> {
+    if len > i32::MAX as usize {
+        return Err(Invalid::new(TagErrorKind::TooManyElements(len)));
+    }
+    Ok(())
+}
+
+fn check_content(content: &TagContent, flavor: NbtFlavor) -> Result<(), Invalid<'_>> {
+    match content {
+        TagContent::End => Err(Invalid::new(TagErrorKind::MisplacedEnd)),
+        TagContent::String(value) => check_string(value, flavor),
+        TagContent::ByteArray(value) => check_len(value.len()),
+        TagContent::IntArray(value) => check_len(value.len()),
+        TagContent::LongArray(value) => check_len(value.len()),
+        TagContent::List(items) => {
+            check_len(items.len())?;
+            let expected = items.first().map(|item| item.content.type_id());
+            for (index, item) in items.iter().enumerate() {
+                let result = if let Some(name) = &item.name {
+                    Err(Invalid::new(TagErrorKind::NamedListElement(name.clone())))
+                } else if Some(item.content.type_id()) != expected {
+                    Err(Invalid::new(TagErrorKind::ListTypeMismatch {
+                        expected: items[0].content.type_name(),
+                        found: item.content.type_name(),
+                    }))
+                } else {
+                    check_content(&item.content, flavor)
+                };
+                result.map_err(|mut e| {
+                    e.segments.push(Segment::Index(index));
+                    e
+                })?;
+            }
+            Ok(())
+        }
+        TagContent::Compound(compound) => {
+            for (key, value) in compound.iter() {
+                check_string(key, flavor)
+                    .and_then(|_| check_content(value, flavor))
+                    .map_err(|mut e| {
+                        e.segments.push(Segment::Key(key));
+                        e
+                    })?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+impl TagContent {
+    /// Checks that the tag can be written: lists are homogeneous with unnamed
+    /// elements, strings fit their length prefix, and end tags only appear as
+    /// the root.
+    pub fn validate(&self) -> TagResult<()> {
+        self.validate_as(NbtFlavor::Java)
+    }
+
+    /// Like [`TagContent::validate`], with the string length limits of `flavor`.
+    pub fn validate_as(&self, flavor: NbtFlavor) -> TagResult<()> {
+        match self {
+            TagContent::End => Ok(()),
+            content => check_content(content, flavor).map_err(Invalid::into_error),
+        }
+    }
+}
+
+impl NBTTag {
+    /// Builds a list tag, checking that all elements share a type and are unnamed.
+    pub fn try_list(name: Option<String>, tags: Vec<NBTTag>) -> TagResult<Self> {
+        let tag = NBTTag::list(name, tags);
+        tag.content.validate()?;
+        Ok(tag)
+    }
+
+    /// Checks the content and, for named roots, the name. See [`TagContent::validate`].
+    pub fn validate(&self) -> TagResult<()> {
+        self.validate_as(NbtFlavor::Java)
+    }
+
+    pub fn validate_as(&self, flavor: NbtFlavor) -> TagResult<()> {
+        if let Some(name) = &self.name {
+            check_string(name, flavor).map_err(Invalid::into_error)?;
+        }
+        self.content.validate_as(flavor)
+    }
+
+    /// Writes the tag as network NBT, leaving the buffer untouched if it is invalid.
+    pub fn try_write(&self, buf: &mut McBuf) -> TagResult<()> {
+        self.content.validate()?;
+        self.content.write_id(buf);
+        self.content.write_content(buf);
+        Ok(())
+    }
+
+    /// Writes the tag with a named root, leaving the buffer untouched if it is invalid.
+    pub fn try_write_named(&self, buf: &mut McBuf) -> TagResult<()> {
+        self.try_write_named_as(buf, NbtFlavor::Java)
+    }
+
+    /// Writes the tag with a named root in any flavor, leaving the buffer untouched if it is invalid.
+    pub fn try_write_named_as(&self, buf: &mut McBuf, flavor: NbtFlavor) -> TagResult<()> {
+        self.validate_as(flavor)?;
+        self.write_named_as(buf, flavor);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use leaflet_network_buffer::{BufferError, McBuf};
+    use crate::error::{TagError, TagErrorKind};
+    use crate::{mutf8, NBTTag, NbtFlavor};
+
+    #[test]
+    fn invalid_network_tag_is_an_error() {
+        let items = vec![NBTTag::int(None, 1), NBTTag::byte(None, 2)];
+        assert!(NBTTag::try_list(None, items.clone()).is_err());
+        let tag = NBTTag::list(None, items);
+
+        let mut buf = McBuf::new();
+        let result = buf.try_write_network_type(&tag);
+        assert!(matches!(result, Err(BufferError::Invalid(message)) if message.contains("[1]")));
+        assert_eq!(buf.remaining(), 0);
+    }
+
+    #[test]
+    fn overlong_strings_are_errors() {
+        let value = format!("{}\u{e9}", "a".repeat(mutf8::MAX_LENGTH - 1));
+        let tag = NBTTag::compound(None, vec![NBTTag::string(Some("name".into()), value.clone())]);
+        let error = TagError { path: "name".into(), kind: TagErrorKind::StringTooLong(mutf8::MAX_LENGTH + 1) };
+        assert_eq!(tag.validate(), Err(error.clone()));
+
+        let mut buf = McBuf::new();
+        assert_eq!(tag.try_write(&mut buf), Err(error));
+        assert_eq!(buf.remaining(), 0);
+
+        // Bedrock network strings have a varint prefix with room for it
+        assert!(tag.validate_as(NbtFlavor::BedrockNetwork).is_ok());
+        assert!(NBTTag::string(Some(value), String::new()).validate().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "too long")]
+    fn unchecked_writes_do_not_cut_strings_short() {
+        let tag = NBTTag::string(None, "a".repeat(mutf8::MAX_LENGTH + 1));
+        tag.content.write_content(&mut McBuf::new());
+    }
+}
@@ -0,0 +1,483 @@
+//! Borrowed views over encoded NBT. Reading a view checks the structure once
+//! without allocating; compounds and lists are then walked lazily and arrays
+//! and ASCII strings are used in place.
+//!
+//! To read from an [`McBuf`](leaflet_network_buffer::McBuf), borrow its unread
+//! bytes and skip them afterwards:
+//!
+//! ```
+//! # use leaflet_nbt::{NbtLimits, NbtRef};
+//! # use leaflet_network_buffer::{BufferResult, McBuf};
+//! # fn main() -> BufferResult<()> {
+//! # let mut buf = McBuf::from_bytes(&[10, 3, 0, 1, b'y', 0, 0, 0, 64, 0]);
+//! let (tag, len) = NbtRef::read_network(buf.unread(), NbtLimits::NETWORK)?;
+//! let y = tag.as_compound().and_then(|compound| compound.get("y"));
+//! assert!(matches!(y, Some(NbtRef::Int(64))));
+//! buf.skip(len)?;
+//! # assert_eq!(buf.remaining(), 0);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+use leaflet_network_buffer::BufferResult;
+use crate::limits::NbtAccounter;
+use crate::reader::{skip, Reader};
+use crate::{NBTTag, NbtCompound, NbtFlavor, NbtLimits, TagContent};
+
+/// A borrowed tag, mirroring [`TagContent`].
+#[derive(Debug, Clone, Copy)]
+pub enum NbtRef<'a> {
+    End,
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    /// The raw bytes, each being one signed byte of the array.
+    ByteArray(&'a [u8]),
+    String(NbtStr<'a>),
+    List(ListRef<'a>),
+    Compound(CompoundRef<'a>),
+    IntArray(IntArrayRef<'a>),
+    LongArray(LongArrayRef<'a>),
+}
+
+/// An encoded string, in Modified UTF-8 for Java and UTF-8 for Bedrock.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NbtStr<'a> {
+    bytes: &'a [u8],
+    flavor: NbtFlavor,
+}
+
+/// A list, whose elements are decoded as they are iterated.
+#[derive(Clone, Copy)]
+pub struct ListRef<'a> {
+    type_id: u8,
+    len: usize,
+    data: &'a [u8],
+    flavor: NbtFlavor,
+}
+
+/// A compound, whose entries are decoded as they are iterated.
+#[derive(Clone, Copy)]
+pub struct CompoundRef<'a> {
+    data: &'a [u8],
+    flavor: NbtFlavor,
+}
+
+/// Decodes the tag at the reader. Its content was validated when the root was read.
+fn read_value<'a>(type_id: u8, reader: &mut Reader<'a>) -> BufferResult<NbtRef<'a>> {
+    let start = reader.bytes;
+    skip(type_id, reader, &mut NbtAccounter::unlimited())?;
+    let data = &start[..start.len() - reader.bytes.len()];
+    let flavor = reader.flavor;
+    let mut content = Reader::new(data, flavor);
+
+    Ok(match type_id {
+        0 => NbtRef::End,
+        1 => NbtRef::Byte(content.byte()?),
+        2 => NbtRef::Short(content.short()?),
+        3 => NbtRef::Int(content.int()?),
+        4 => NbtRef::Long(content.long()?),
+        5 => NbtRef::Float(content.float()?),
+        6 => NbtRef::Double(content.double()?),
+        7 => {
+            let len = content.length()?;
+            NbtRef::ByteArray(content.take(len)?)
+        }
+        8 => NbtRef::String(NbtStr { bytes: content.string(&NbtAccounter::unlimited())?, flavor }),
+        9 => {
+            let type_id = content.u8()?;
+            let len = content.length()?;
+            NbtRef::List(ListRef { type_id, len, data: content.bytes, flavor })
+        }
+        10 => NbtRef::Compound(CompoundRef { data, flavor }),
+        11 => {
+            let len = content.length()?;
+            NbtRef::IntArray(IntArrayRef { len, data: content.bytes, flavor })
+        }
+        _ => {
+            let len = content.length()?;
+            NbtRef::LongArray(LongArrayRef { len, data: content.bytes, flavor })
+        }
+    })
+}
+
+impl<'a> NbtRef<'a> {
+    /// Borrows a tag with a nameless network root from the start of `bytes`,
+    /// returning it and the number of bytes it takes up.
+    pub fn read_network(bytes: &'a [u8], limits: NbtLimits) -> BufferResult<(NbtRef<'a>, usize)> {
+        let mut reader = Reader::new(bytes, NbtFlavor::Java);
+        let mut accounter = NbtAccounter::new(limits, bytes.len());
+        let type_id = reader.u8()?;
+        let mut content = reader.clone();
+        skip(type_id, &mut reader, &mut accounter)?;
+        Ok((read_value(type_id, &mut content)?, bytes.len() - reader.bytes.len()))
+    }
+
+    /// Borrows a tag with a named root, as used by NBT files, from the start of
+    /// `bytes`, returning its name, the tag and the number of bytes it takes up.
+    pub fn read_named(bytes: &'a [u8], limits: NbtLimits) -> BufferResult<(NbtStr<'a>, NbtRef<'a>, usize)> {
+        NbtRef::read_named_as(bytes, NbtFlavor::Java, limits)
+    }
+
+    /// Like [`NbtRef::read_named`], in any flavor. Bedrock always uses named roots.
+    pub fn read_named_as(bytes: &'a [u8], flavor: NbtFlavor, limits: NbtLimits) -> BufferResult<(NbtStr<'a>, NbtRef<'a>, usize)> {
+        let mut reader = Reader::new(bytes, flavor);
+        let mut accounter = NbtAccounter::new(limits, bytes.len());
+        let type_id = reader.u8()?;
+        let name = NbtStr { bytes: reader.string(&accounter)?, flavor };
+        let mut content = reader.clone();
+        skip(type_id, &mut reader, &mut accounter)?;
+        Ok((name, read_value(type_id, &mut content)?, bytes.len() - reader.bytes.len()))
+    }
+
+    pub fn type_id(&self) -> u8 {
+        match self {
+            NbtRef::End => 0,
+            NbtRef::Byte(_) => 1,
+            NbtRef::Short(_) => 2,
+            NbtRef::Int(_) => 3,
+            NbtRef::Long(_) => 4,
+            NbtRef::Float(_) => 5,
+            NbtRef::Double(_) => 6,
+            NbtRef::ByteArray(_) => 7,
+            NbtRef::String(_) => 8,
+            NbtRef::List(_) => 9,
+            NbtRef::Compound(_) => 10,
+            NbtRef::IntArray(_) => 11,
+            NbtRef::LongArray(_) => 12,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<CompoundRef<'a>> {
+        match self {
+            NbtRef::Compound(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<ListRef<'a>> {
+        match self {
+            NbtRef::List(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Copies the tag into an owned [`TagContent`]. Fails only if a string is
+    /// not validly encoded.
+    pub fn to_content(&self) -> BufferResult<TagContent> {
+        Ok(match self {
+            NbtRef::End => TagContent::End,
+            NbtRef::Byte(value) => TagContent::Byte(*value),
+            NbtRef::Short(value) => TagContent::Short(*value),
+            NbtRef::Int(value) => TagContent::Int(*value),
+            NbtRef::Long(value) => TagContent::Long(*value),
+            NbtRef::Float(value) => TagContent::Float(*value),
+            NbtRef::Double(value) => TagContent::Double(*value),
+            NbtRef::ByteArray(value) => TagContent::ByteArray(value.iter().map(|b| *b as i8).collect()),
+            NbtRef::String(value) => TagContent::String(value.to_str()?.into_owned()),
+            NbtRef::List(value) => TagContent::List(
+                value
+                    .iter()
+                    .map(|item| Ok(NBTTag { content: item.to_content()?, name: None }))
+                    .collect::<BufferResult<_>>()?,
+            ),
+            NbtRef::Compound(value) => TagContent::Compound(value.to_compound()?),
+            NbtRef::IntArray(value) => TagContent::IntArray(value.iter().collect()),
+            NbtRef::LongArray(value) => TagContent::LongArray(value.iter().collect()),
+        })
+    }
+}
+
+impl<'a> NbtStr<'a> {
+    /// The encoded bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes the string. ASCII strings, and all Bedrock strings, are borrowed without copying.
+    pub fn to_str(&self) -> BufferResult<Cow<'a, str>> {
+        self.flavor.decode_string(self.bytes)
+    }
+}
+
+impl Debug for NbtStr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.to_str() {
+            Ok(value) => Debug::fmt(&value, f),
+            Err(_) => write!(f, "<invalid {:?}>", self.bytes),
+        }
+    }
+}
+
+impl<'a> ListRef<'a> {
+    /// Type id shared by all elements, or 0 for an empty list.
+    pub fn element_type(&self) -> u8 {
+        self.type_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets an element. This is constant time for fixed-size numeric elements
+    /// and walks the preceding elements otherwise.
+    pub fn get(&self, index: usize) -> Option<NbtRef<'a>> {
+        if index >= self.len {
+            return None;
+        }
+        match self.flavor.fixed_size(self.type_id) {
+            Some(size) => {
+                let mut reader = Reader::new(&self.data[index * size..], self.flavor);
+                read_value(self.type_id, &mut reader).ok()
+            }
+            None => self.iter().nth(index),
+        }
+    }
+
+    pub fn iter(&self) -> ListIter<'a> {
+        ListIter { type_id: self.type_id, remaining: self.len, reader: Reader::new(self.data, self.flavor) }
+    }
+}
+
+impl Debug for ListRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for ListRef<'a> {
+    type Item = NbtRef<'a>;
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct ListIter<'a> {
+    type_id: u8,
+    remaining: usize,
+    reader: Reader<'a>,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = NbtRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        read_value(self.type_id, &mut self.reader).ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ListIter<'_> {}
+
+impl<'a> CompoundRef<'a> {
+    pub fn iter(&self) -> CompoundIter<'a> {
+        CompoundIter { reader: Reader::new(self.data, self.flavor) }
+    }
+
+    /// Finds an entry by name, walking the entries before it.
+    pub fn get(&self, key: &str) -> Option<NbtRef<'a>> {
+        let key = self.flavor.encode_string(key).ok()?;
+        self.iter().find(|(name, _)| name.as_bytes() == key.as_ref()).map(|(_, value)| value)
+    }
+
+    pub fn get_compound(&self, key: &str) -> Option<CompoundRef<'a>> {
+        self.get(key)?.as_compound()
+    }
+
+    pub fn get_list(&self, key: &str) -> Option<ListRef<'a>> {
+        self.get(key)?.as_list()
+    }
+
+    /// Copies the compound into an owned [`NbtCompound`].
+    pub fn to_compound(&self) -> BufferResult<NbtCompound> {
+        self.iter()
+            .map(|(name, value)| Ok((name.to_str()?.into_owned(), value.to_content()?)))
+            .collect()
+    }
+}
+
+impl Debug for CompoundRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for CompoundRef<'a> {
+    type Item = (NbtStr<'a>, NbtRef<'a>);
+    type IntoIter = CompoundIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct CompoundIter<'a> {
+    reader: Reader<'a>,
+}
+
+impl<'a> Iterator for CompoundIter<'a> {
+    type Item = (NbtStr<'a>, NbtRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let type_id = self.reader.u8().ok()?;
+        if type_id == 0 {
+            return None;
+        }
+        let name = NbtStr { bytes: self.reader.string(&NbtAccounter::unlimited()).ok()?, flavor: self.reader.flavor };
+        let value = read_value(type_id, &mut self.reader).ok()?;
+        Some((name, value))
+    }
+}
+
+macro_rules! array_ref {
+    ($name:ident, $ty:ty, $type_id:literal, $read:ident) => {
+        /// An array, decoded as it is read.
+        #[derive(Clone, Copy)]
+        pub struct $name<'a> {
+            pub(crate) len: usize,
+            pub(crate) data: &'a [u8],
+            pub(crate) flavor: NbtFlavor,
+        }
+
+        impl<'a> $name<'a> {
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// Gets an element. This is constant time unless the elements are varints.
+            pub fn get(&self, index: usize) -> Option<$ty> {
+                if index >= self.len {
+                    return None;
+                }
+                match self.flavor.fixed_size($type_id) {
+                    Some(size) => Reader::new(&self.data[index * size..], self.flavor).$read().ok(),
+                    None => self.iter().nth(index),
+                }
+            }
+
+            pub fn iter(&self) -> impl ExactSizeIterator<Item = $ty> + 'a {
+                let mut reader = Reader::new(self.data, self.flavor);
+                (0..self.len).map(move |_| reader.$read().unwrap_or_default())
+            }
+        }
+
+        impl Debug for $name<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+    };
+}
+
+array_ref!(IntArrayRef, i32, 3, int);
+array_ref!(LongArrayRef, i64, 4, long);
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::ops::Range;
+    use leaflet_network_buffer::McBuf;
+    use crate::{NBTTag, NbtLimits};
+    use super::NbtRef;
+
+    fn encode_network(tag: &NBTTag) -> Vec<u8> {
+        let mut buf = McBuf::new();
+        tag.try_write(&mut buf).unwrap();
+        buf.as_slice().to_vec()
+    }
+
+    fn chunk() -> NBTTag {
+        NBTTag::from_snbt(
+            r#"{Status: "minecraft:full", xPos: -3, sections: [{Y: 0b, data: [L; 1L, 2L]}, {Y: 1b, data: [L;]}], Heightmaps: {WORLD_SURFACE: [I; 64, 65]}, blocks: [B; 1B, 2B, 3B], "caf\u00e9": 1.5d}"#,
+        )
+        .unwrap()
+    }
+
+    fn within(slice: &[u8], range: &Range<*const u8>) -> bool {
+        slice.is_empty() || range.contains(&slice.as_ptr())
+    }
+
+    #[test]
+    fn matches_the_owned_decoder() {
+        let data = encode_network(&chunk());
+        let mut buf = McBuf::from_bytes(&data);
+        let owned = NBTTag::read_network_limited(&mut buf, NbtLimits::NETWORK).unwrap();
+
+        let (view, len) = NbtRef::read_network(&data, NbtLimits::NETWORK).unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(view.to_content().unwrap(), owned.content);
+
+        let compound = view.as_compound().unwrap();
+        assert!(matches!(compound.get("xPos"), Some(NbtRef::Int(-3))));
+        let sections = compound.get_list("sections").unwrap();
+        assert_eq!(sections.len(), 2);
+        assert!(matches!(sections.get(1).and_then(|section| section.as_compound()?.get("Y")), Some(NbtRef::Byte(1))));
+        assert!(compound.get("missing").is_none());
+    }
+
+    #[test]
+    fn matches_the_owned_decoder_with_named_roots() {
+        let mut buf = McBuf::new();
+        let mut tag = chunk();
+        tag.name = Some("root".into());
+        tag.try_write_named(&mut buf).unwrap();
+        let data = buf.as_slice().to_vec();
+
+        let (name, view, len) = NbtRef::read_named(&data, NbtLimits::FILE).unwrap();
+        assert_eq!(name.to_str().unwrap(), "root");
+        assert_eq!(len, data.len());
+        assert_eq!(view.to_content().unwrap(), tag.content);
+    }
+
+    #[test]
+    fn borrows_without_copying() {
+        let data = encode_network(&chunk());
+        let range = data.as_ptr_range();
+        let (view, _) = NbtRef::read_network(&data, NbtLimits::NETWORK).unwrap();
+        let compound = view.as_compound().unwrap();
+
+        for (name, _) in compound.iter() {
+            assert!(within(name.as_bytes(), &range));
+        }
+        let Some(NbtRef::String(status)) = compound.get("Status") else { panic!("Status is not a string") };
+        assert!(within(status.as_bytes(), &range));
+        assert!(matches!(status.to_str().unwrap(), Cow::Borrowed("minecraft:full")));
+        let Some(NbtRef::ByteArray(blocks)) = compound.get("blocks") else { panic!("blocks is not a byte array") };
+        assert!(within(blocks, &range));
+        assert_eq!(blocks, [1, 2, 3]);
+
+        // Modified UTF-8 has to be decoded, so only that is copied
+        let (name, _) = compound.iter().last().unwrap();
+        assert!(matches!(name.to_str().unwrap(), Cow::Owned(name) if name == "caf\u{e9}"));
+    }
+
+    #[test]
+    fn applies_limits_before_borrowing() {
+        let data = encode_network(&chunk());
+        let limits = NbtLimits { max_bytes: data.len() / 2, ..NbtLimits::NETWORK };
+        assert!(NbtRef::read_network(&data, limits).is_err());
+        assert!(NbtRef::read_network(&data[..data.len() - 1], NbtLimits::NETWORK).is_err());
+    }
+}
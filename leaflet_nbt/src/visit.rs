@@ -0,0 +1,612 @@
+//! Streaming NBT decoding. The reader walks the encoded tag and reports each
+//! value to an [`NbtVisitor`], which can skip compound entries, lists and
+//! compounds it has no interest in. Skipped subtrees are still checked against
+//! the [`NbtLimits`] but nothing is allocated for them.
+
+use std::mem;
+use leaflet_network_buffer::{BufferError, BufferResult, McBuf};
+use crate::limits::NbtAccounter;
+use crate::reader::{skip, skip_elements, Reader};
+use crate::view::{IntArrayRef, LongArrayRef};
+use crate::{NBTTag, NbtCompound, NbtFlavor, NbtLimits, TagContent};
+
+/// Whether the reader should descend into a value or skip over it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Enter,
+    Skip,
+}
+
+/// Callbacks for a tag as it is decoded. All methods do nothing by default,
+/// and all values are entered.
+///
+/// Compound entries are reported with [`visit_entry`](NbtVisitor::visit_entry)
+/// before their value, and lists report their elements in order between
+/// [`visit_list_start`](NbtVisitor::visit_list_start) and
+/// [`visit_list_end`](NbtVisitor::visit_list_end).
+#[allow(unused_variables)]
+pub trait NbtVisitor {
+    /// Called for a compound entry, or a named root, before its value.
+    fn visit_entry(&mut self, name: &str, type_id: u8) -> Visit {
+        Visit::Enter
+    }
+
+    /// Called for an end tag at the root, which stands for an empty tag.
+    fn visit_end(&mut self) {}
+
+    fn visit_byte(&mut self, value: i8) {}
+
+    fn visit_short(&mut self, value: i16) {}
+
+    fn visit_int(&mut self, value: i32) {}
+
+    fn visit_long(&mut self, value: i64) {}
+
+    fn visit_float(&mut self, value: f32) {}
+
+    fn visit_double(&mut self, value: f64) {}
+
+    /// The raw bytes, each being one signed byte of the array.
+    fn visit_byte_array(&mut self, value: &[u8]) {}
+
+    fn visit_string(&mut self, value: &str) {}
+
+    fn visit_int_array(&mut self, value: IntArrayRef<'_>) {}
+
+    fn visit_long_array(&mut self, value: LongArrayRef<'_>) {}
+
+    /// Called before the elements of a list. `element_type` is 0 for an empty list.
+    fn visit_list_start(&mut self, element_type: u8, len: usize) -> Visit {
+        Visit::Enter
+    }
+
+    fn visit_list_end(&mut self) {}
+
+    fn visit_compound_start(&mut self) -> Visit {
+        Visit::Enter
+    }
+
+    fn visit_compound_end(&mut self) {}
+}
+
+struct Driver<'a, 'v, V: ?Sized> {
+    reader: Reader<'a>,
+    accounter: NbtAccounter,
+    visitor: &'v mut V,
+}
+
+impl<'a, V: NbtVisitor + ?Sized> Driver<'a, '_, V> {
+    fn value(&mut self, type_id: u8) -> BufferResult<()> {
+        match type_id {
+            0 => self.visitor.visit_end(),
+            1 => self.visitor.visit_byte(self.reader.byte()?),
+            2 => self.visitor.visit_short(self.reader.short()?),
+            3 => self.visitor.visit_int(self.reader.int()?),
+            4 => self.visitor.visit_long(self.reader.long()?),
+            5 => self.visitor.visit_float(self.reader.float()?),
+            6 => self.visitor.visit_double(self.reader.double()?),
+            7 => {
+                let len = self.reader.length()?;
+                self.accounter.reserve_bytes(self.reader.bytes.len(), len)?;
+                let value = self.reader.take(len)?;
+                self.visitor.visit_byte_array(value);
+            }
+            8 => {
+                let value = self.reader.string(&self.accounter)?;
+                self.visitor.visit_string(&self.reader.flavor.decode_string(value)?);
+            }
+            9 => self.list()?,
+            10 => self.compound()?,
+            11 | 12 => self.array(type_id)?,
+            _ => return Err(BufferError::Generic("Unknown tag type")),
+        }
+        Ok(())
+    }
+
+    fn array(&mut self, type_id: u8) -> BufferResult<()> {
+        // Skip first so the length is checked before anything looks at the elements
+        let mut start = self.reader.clone();
+        skip(type_id, &mut self.reader, &mut self.accounter)?;
+        let len = start.length()?;
+        let data = &start.bytes[..start.bytes.len() - self.reader.bytes.len()];
+        let flavor = self.reader.flavor;
+        if type_id == 11 {
+            self.visitor.visit_int_array(IntArrayRef { len, data, flavor });
+        } else {
+            self.visitor.visit_long_array(LongArrayRef { len, data, flavor });
+        }
+        Ok(())
+    }
+
+    // Lists and compounds are read in their own functions to keep the
+    // recursive frame small, so the depth limit is hit before the stack runs out
+    fn list(&mut self) -> BufferResult<()> {
+        let type_id = self.reader.u8()?;
+        let len = self.reader.length()?;
+        if type_id == 0 && len > 0 {
+            return Err(BufferError::Generic("List of end tags"));
+        }
+        // Every element takes at least one byte and counts towards the element
+        // limit, so visitors can safely allocate for `len` elements
+        self.accounter.reserve_bytes(self.reader.bytes.len(), len)?;
+        self.accounter.count_elements(self.reader.bytes.len(), len)?;
+        self.accounter.push_depth()?;
+        if self.visitor.visit_list_start(type_id, len) == Visit::Skip {
+            skip_elements(type_id, len, &mut self.reader, &mut self.accounter)?;
+            self.accounter.pop_depth();
+            return Ok(());
+        }
+
+        for _ in 0..len {
+            self.value(type_id)?;
+        }
+        self.accounter.pop_depth();
+        self.visitor.visit_list_end();
+        Ok(())
+    }
+
+    fn compound(&mut self) -> BufferResult<()> {
+        if self.visitor.visit_compound_start() == Visit::Skip {
+            return skip(10, &mut self.reader, &mut self.accounter);
+        }
+
+        self.accounter.push_depth()?;
+        loop {
+            let type_id = self.reader.u8()?;
+            if type_id == 0 {
+                break
+            }
+
+            self.accounter.count_elements(self.reader.bytes.len(), 1)?;
+            let name = self.reader.string(&self.accounter)?;
+            match self.visitor.visit_entry(&self.reader.flavor.decode_string(name)?, type_id) {
+                Visit::Enter => self.value(type_id)?,
+                Visit::Skip => skip(type_id, &mut self.reader, &mut self.accounter)?,
+            }
+        }
+        self.accounter.pop_depth();
+        self.visitor.visit_compound_end();
+        Ok(())
+    }
+}
+
+/// Runs `f` over the unread part of the buffer, advancing the buffer past
+/// what was read if it succeeds.
+fn drive<V: NbtVisitor + ?Sized>(
+    buf: &mut McBuf,
+    visitor: &mut V,
+    flavor: NbtFlavor,
+    limits: NbtLimits,
+    f: impl FnOnce(&mut Driver<'_, '_, V>) -> BufferResult<()>,
+) -> BufferResult<()> {
+    let bytes = buf.unread();
+    let mut driver = Driver {
+        reader: Reader::new(bytes, flavor),
+        accounter: NbtAccounter::new(limits, bytes.len()),
+        visitor,
+    };
+    f(&mut driver)?;
+    let consumed = bytes.len() - driver.reader.bytes.len();
+    buf.skip(consumed)
+}
+
+/// Visits the content of a tag whose type is already known.
+pub fn visit_content<V: NbtVisitor + ?Sized>(type_id: u8, buf: &mut McBuf, visitor: &mut V, limits: NbtLimits) -> BufferResult<()> {
+    visit_content_as(type_id, buf, visitor, NbtFlavor::Java, limits)
+}
+
+pub fn visit_content_as<V: NbtVisitor + ?Sized>(
+    type_id: u8,
+    buf: &mut McBuf,
+    visitor: &mut V,
+    flavor: NbtFlavor,
+    limits: NbtLimits,
+) -> BufferResult<()> {
+    drive(buf, visitor, flavor, limits, |driver| driver.value(type_id))
+}
+
+/// Visits a tag with a nameless Java network root.
+pub fn visit_network<V: NbtVisitor + ?Sized>(buf: &mut McBuf, visitor: &mut V, limits: NbtLimits) -> BufferResult<()> {
+    drive(buf, visitor, NbtFlavor::Java, limits, |driver| {
+        let type_id = driver.reader.u8()?;
+        driver.value(type_id)
+    })
+}
+
+/// Visits a tag with a named root, as used by NBT files. The root name is
+/// reported with [`NbtVisitor::visit_entry`].
+pub fn visit_named<V: NbtVisitor + ?Sized>(buf: &mut McBuf, visitor: &mut V, limits: NbtLimits) -> BufferResult<()> {
+    visit_named_as(buf, visitor, NbtFlavor::Java, limits)
+}
+
+/// Like [`visit_named`], in any flavor. Bedrock always uses named roots.
+pub fn visit_named_as<V: NbtVisitor + ?Sized>(buf: &mut McBuf, visitor: &mut V, flavor: NbtFlavor, limits: NbtLimits) -> BufferResult<()> {
+    drive(buf, visitor, flavor, limits, |driver| {
+        let type_id = driver.reader.u8()?;
+        let name = driver.reader.string(&driver.accounter)?;
+        match driver.visitor.visit_entry(&driver.reader.flavor.decode_string(name)?, type_id) {
+            Visit::Enter => driver.value(type_id),
+            Visit::Skip => skip(type_id, &mut driver.reader, &mut driver.accounter),
+        }
+    })
+}
+
+enum Frame {
+    List(Vec<NBTTag>),
+    /// A compound and the name of the entry being read.
+    Compound(NbtCompound, String),
+}
+
+/// Visitor building an owned tag tree.
+#[derive(Default)]
+pub(crate) struct TreeBuilder {
+    stack: Vec<Frame>,
+    root: Option<TagContent>,
+    root_name: Option<String>,
+}
+
+impl TreeBuilder {
+    fn push(&mut self, content: TagContent) {
+        match self.stack.last_mut() {
+            Some(Frame::List(items)) => items.push(NBTTag { content, name: None }),
+            Some(Frame::Compound(compound, name)) => {
+                compound.insert(mem::take(name), content);
+            }
+            None => self.root = Some(content),
+        }
+    }
+
+    pub(crate) fn finish(self) -> NBTTag {
+        NBTTag {
+            content: self.root.unwrap_or(TagContent::End),
+            name: self.root_name.filter(|name| !name.is_empty()),
+        }
+    }
+}
+
+impl NbtVisitor for TreeBuilder {
+    fn visit_entry(&mut self, name: &str, _type_id: u8) -> Visit {
+        match self.stack.last_mut() {
+            Some(Frame::Compound(_, entry)) => *entry = name.to_string(),
+            _ => self.root_name = Some(name.to_string()),
+        }
+        Visit::Enter
+    }
+
+    fn visit_end(&mut self) {
+        self.push(TagContent::End)
+    }
+
+    fn visit_byte(&mut self, value: i8) {
+        self.push(TagContent::Byte(value))
+    }
+
+    fn visit_short(&mut self, value: i16) {
+        self.push(TagContent::Short(value))
+    }
+
+    fn visit_int(&mut self, value: i32) {
+        self.push(TagContent::Int(value))
+    }
+
+    fn visit_long(&mut self, value: i64) {
+        self.push(TagContent::Long(value))
+    }
+
+    fn visit_float(&mut self, value: f32) {
+        self.push(TagContent::Float(value))
+    }
+
+    fn visit_double(&mut self, value: f64) {
+        self.push(TagContent::Double(value))
+    }
+
+    fn visit_byte_array(&mut self, value: &[u8]) {
+        self.push(TagContent::ByteArray(value.iter().map(|b| *b as i8).collect()))
+    }
+
+    fn visit_string(&mut self, value: &str) {
+        self.push(TagContent::String(value.to_string()))
+    }
+
+    fn visit_int_array(&mut self, value: IntArrayRef<'_>) {
+        self.push(TagContent::IntArray(value.iter().collect()))
+    }
+
+    fn visit_long_array(&mut self, value: LongArrayRef<'_>) {
+        self.push(TagContent::LongArray(value.iter().collect()))
+    }
+
+    fn visit_list_start(&mut self, _element_type: u8, len: usize) -> Visit {
+        self.stack.push(Frame::List(Vec::with_capacity(len)));
+        Visit::Enter
+    }
+
+    fn visit_list_end(&mut self) {
+        if let Some(Frame::List(items)) = self.stack.pop() {
+            self.push(TagContent::List(items))
+        }
+    }
+
+    fn visit_compound_start(&mut self) -> Visit {
+        self.stack.push(Frame::Compound(NbtCompound::new(), String::new()));
+        Visit::Enter
+    }
+
+    fn visit_compound_end(&mut self) {
+        if let Some(Frame::Compound(compound, _)) = self.stack.pop() {
+            self.push(TagContent::Compound(compound))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use leaflet_network_buffer::{BufferError, BufferResult, McBuf};
+    use crate::{mutf8, NBTTag, NbtCompound, NbtLimits, TagContent};
+    use super::{visit_named, visit_network, NbtVisitor, Visit};
+
+    fn limits(max_depth: usize, max_bytes: usize, max_elements: usize) -> NbtLimits {
+        NbtLimits { max_depth, max_bytes, max_elements }
+    }
+
+    fn read(data: &[u8], limits: NbtLimits) -> BufferResult<NBTTag> {
+        NBTTag::read_network_limited(&mut McBuf::from_bytes(data), limits)
+    }
+
+    /// A network root list holding `depth` lists nested inside each other,
+    /// encoded by hand so the test does not recurse as deep as the reader.
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut data = vec![9];
+        for _ in 1..depth {
+            data.extend([9, 0, 0, 0, 1]);
+        }
+        data.extend([1, 0, 0, 0, 0]);
+        data
+    }
+
+    /// A network root list of `len` bytes.
+    fn byte_list(len: usize) -> Vec<u8> {
+        let mut data = vec![9, 1];
+        data.extend((len as i32).to_be_bytes());
+        data.resize(data.len() + len, 0);
+        data
+    }
+
+    #[derive(Default)]
+    struct ListStarts(usize);
+
+    /// Records the values it sees, skipping entries named `skip` and, if
+    /// `skip_containers` is set, every list and compound below the root.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        skip_containers: bool,
+        depth: usize,
+    }
+
+    impl Recorder {
+        fn enter(&mut self, event: String) -> Visit {
+            self.events.push(event);
+            if self.skip_containers && self.depth > 0 {
+                return Visit::Skip;
+            }
+            self.depth += 1;
+            Visit::Enter
+        }
+    }
+
+    impl NbtVisitor for Recorder {
+        fn visit_entry(&mut self, name: &str, _type_id: u8) -> Visit {
+            self.events.push(format!("entry {name}"));
+            if name == "skip" { Visit::Skip } else { Visit::Enter }
+        }
+
+        fn visit_int(&mut self, value: i32) {
+            self.events.push(format!("int {value}"));
+        }
+
+        fn visit_string(&mut self, value: &str) {
+            self.events.push(format!("string {value}"));
+        }
+
+        fn visit_list_start(&mut self, _element_type: u8, len: usize) -> Visit {
+            self.enter(format!("list {len}"))
+        }
+
+        fn visit_list_end(&mut self) {
+            self.depth -= 1;
+            self.events.push("list end".into());
+        }
+
+        fn visit_compound_start(&mut self) -> Visit {
+            self.enter("compound".into())
+        }
+
+        fn visit_compound_end(&mut self) {
+            self.depth -= 1;
+            self.events.push("compound end".into());
+        }
+    }
+
+    fn record(data: &[u8], visitor: &mut Recorder, limits: NbtLimits) -> BufferResult<usize> {
+        let mut buf = McBuf::from_bytes(data);
+        visit_network(&mut buf, visitor, limits)?;
+        Ok(data.len() - buf.remaining())
+    }
+
+    fn encode_network(tag: &NBTTag) -> Vec<u8> {
+        let mut buf = McBuf::new();
+        tag.try_write(&mut buf).unwrap();
+        buf.as_slice().to_vec()
+    }
+
+    /// The recursive decoder the tree builder replaced, without the limits.
+    fn read_directly(type_id: u8, buf: &mut McBuf) -> TagContent {
+        let length = |buf: &mut McBuf| buf.read_int().unwrap() as usize;
+        let string = |buf: &mut McBuf| {
+            let len = buf.read_ushort().unwrap() as usize;
+            mutf8::decode(&buf.read_dyn_array(len).unwrap()).unwrap().into_owned()
+        };
+        match type_id {
+            0 => TagContent::End,
+            1 => TagContent::Byte(buf.read_byte().unwrap()),
+            2 => TagContent::Short(buf.read_short().unwrap()),
+            3 => TagContent::Int(buf.read_int().unwrap()),
+            4 => TagContent::Long(buf.read_long().unwrap()),
+            5 => TagContent::Float(buf.read_float().unwrap()),
+            6 => TagContent::Double(buf.read_double().unwrap()),
+            7 => TagContent::ByteArray((0..length(buf)).map(|_| buf.read_byte().unwrap()).collect()),
+            8 => TagContent::String(string(buf)),
+            9 => {
+                let type_id = buf.read_u8().unwrap();
+                let items = (0..length(buf)).map(|_| NBTTag { content: read_directly(type_id, buf), name: None }).collect();
+                TagContent::List(items)
+            }
+            10 => {
+                let mut compound = NbtCompound::new();
+                loop {
+                    let type_id = buf.read_u8().unwrap();
+                    if type_id == 0 {
+                        break TagContent::Compound(compound);
+                    }
+                    let name = string(buf);
+                    compound.insert(name, read_directly(type_id, buf));
+                }
+            }
+            11 => TagContent::IntArray((0..length(buf)).map(|_| buf.read_int().unwrap()).collect()),
+            _ => TagContent::LongArray((0..length(buf)).map(|_| buf.read_long().unwrap()).collect()),
+        }
+    }
+
+    fn sample() -> NBTTag {
+        let mut tag = NBTTag::from_snbt(
+            r#"{byte: 1b, short: 2s, int: 3, long: 4L, float: 5.5f, double: 6.5d, bytes: [B; 1B, -1B], ints: [I; 7], longs: [L; 8L, 9L],
+                empty: [], lists: [[1, 2], ["a"], []], compounds: [{}, {a: {b: [I;]}}]}"#,
+        )
+        .unwrap();
+        let TagContent::Compound(compound) = &mut tag.content else { unreachable!() };
+        compound.insert("caf\u{e9}", TagContent::String("\0 \u{1F600}".into()));
+        tag
+    }
+
+    impl NbtVisitor for ListStarts {
+        fn visit_list_start(&mut self, _element_type: u8, _len: usize) -> Visit {
+            self.0 += 1;
+            Visit::Enter
+        }
+    }
+
+    #[test]
+    fn limits_depth() {
+        assert!(read(&nested_lists(3), limits(3, 1024, 1024)).is_ok());
+        assert!(matches!(read(&nested_lists(4), limits(3, 1024, 1024)), Err(BufferError::DepthLimitExceeded)));
+
+        // The real limit fits on a test thread's stack
+        let depth = NbtLimits::NETWORK.max_depth;
+        assert!(read(&nested_lists(depth), NbtLimits::NETWORK).is_ok());
+        assert!(matches!(read(&nested_lists(depth + 1), NbtLimits::NETWORK), Err(BufferError::DepthLimitExceeded)));
+    }
+
+    #[test]
+    fn limits_size() {
+        // A root byte array of 32 bytes takes up 37
+        let mut data = vec![7, 0, 0, 0, 32];
+        data.resize(37, 0);
+        assert!(read(&data, limits(4, 37, 4)).is_ok());
+        assert!(matches!(read(&data, limits(4, 36, 4)), Err(BufferError::SizeLimitExceeded)));
+
+        // Lengths past the limit fail as such, even when the input is cut short
+        assert!(matches!(read(&[8, 0xFF, 0xFF], limits(4, 64, 4)), Err(BufferError::SizeLimitExceeded)));
+    }
+
+    #[test]
+    fn limits_elements() {
+        assert!(read(&byte_list(4), limits(4, 1024, 4)).is_ok());
+        assert!(matches!(read(&byte_list(5), limits(4, 1024, 4)), Err(BufferError::ElementLimitExceeded)));
+
+        // Compound entries count too
+        let data = [10, 1, 0, 1, b'a', 0, 1, 0, 1, b'b', 0, 0];
+        assert!(read(&data, limits(4, 1024, 2)).is_ok());
+        assert!(matches!(read(&data, limits(4, 1024, 1)), Err(BufferError::ElementLimitExceeded)));
+    }
+
+    #[test]
+    fn checks_list_length_before_visiting_it() {
+        let mut visitor = ListStarts::default();
+        let result = visit_network(&mut McBuf::from_bytes(&byte_list(5)), &mut visitor, limits(4, 1024, 4));
+        assert!(matches!(result, Err(BufferError::ElementLimitExceeded)));
+        assert_eq!(visitor.0, 0);
+    }
+
+    #[test]
+    fn skips_subtrees() {
+        let tag = NBTTag::from_snbt(r#"{a: 1, skip: {b: 2, c: [3, 4]}, d: [{e: 5}], f: "after"}"#).unwrap();
+        let data = encode_network(&tag);
+
+        let mut visitor = Recorder::default();
+        assert_eq!(record(&data, &mut visitor, NbtLimits::NETWORK).unwrap(), data.len());
+        assert_eq!(visitor.events, [
+            "compound", "entry a", "int 1", "entry skip", "entry d", "list 1", "compound", "entry e", "int 5",
+            "compound end", "list end", "entry f", "string after", "compound end",
+        ]);
+
+        let mut visitor = Recorder { skip_containers: true, ..Recorder::default() };
+        assert_eq!(record(&data, &mut visitor, NbtLimits::NETWORK).unwrap(), data.len());
+        assert_eq!(visitor.events, [
+            "compound", "entry a", "int 1", "entry skip", "entry d", "list 1", "entry f", "string after", "compound end",
+        ]);
+    }
+
+    #[test]
+    fn skipped_subtrees_count_towards_the_limits() {
+        let tag = NBTTag::from_snbt(r#"{skip: {a: [[[1]]], b: 2, c: [3, 4, 5]}}"#).unwrap();
+        let data = encode_network(&tag);
+        let limits = |max_depth, max_bytes, max_elements| NbtLimits { max_depth, max_bytes, max_elements };
+
+        let mut visitor = Recorder::default();
+        assert!(record(&data, &mut visitor, limits(5, data.len(), 10)).is_ok());
+
+        for (limits, error) in [
+            (limits(4, data.len(), 10), BufferError::DepthLimitExceeded),
+            (limits(5, data.len() / 2, 10), BufferError::SizeLimitExceeded),
+            (limits(5, data.len(), 9), BufferError::ElementLimitExceeded),
+        ] {
+            for skip_containers in [false, true] {
+                let mut visitor = Recorder { skip_containers, ..Recorder::default() };
+                let result = record(&data, &mut visitor, limits);
+                assert_eq!(std::mem::discriminant(&result.unwrap_err()), std::mem::discriminant(&error));
+                assert!(!visitor.events.contains(&"int 1".to_string()));
+            }
+        }
+    }
+
+    #[test]
+    fn builds_the_same_tree_as_reading_directly() {
+        let data = encode_network(&sample());
+        let mut buf = McBuf::from_bytes(&data);
+        let type_id = buf.read_u8().unwrap();
+        let direct = read_directly(type_id, &mut buf);
+
+        let built = read(&data, NbtLimits::NETWORK).unwrap();
+        assert_eq!(built.content, direct);
+        assert_eq!(built.content, sample().content);
+        assert_eq!(built.name, None);
+    }
+
+    #[test]
+    fn builds_the_same_tree_with_named_roots() {
+        let mut tag = sample();
+        tag.name = Some("Level".into());
+        let mut buf = McBuf::new();
+        tag.try_write_named(&mut buf).unwrap();
+
+        let built = NBTTag::read_named_limited(&mut McBuf::from_bytes(buf.as_slice()), NbtLimits::FILE).unwrap();
+        assert_eq!(built, tag);
+
+        // The root name is reported before its value
+        let mut visitor = Recorder::default();
+        visit_named(&mut buf, &mut visitor, NbtLimits::FILE).unwrap();
+        assert_eq!(visitor.events[..2], ["entry Level", "compound"]);
+    }
+}
@@ -1,9 +1,9 @@
 use uuid::Uuid;
-use crate::{BufferResult, McBuf, NetworkType};
+use crate::{BufferError, BufferResult, McBuf, NetworkType};
 
 impl NetworkType for String {
     fn read(buf: &mut McBuf) -> BufferResult<Self> {
-        Ok(buf.read_string(32767)?)
+        buf.read_string(32767)
     }
 
     fn write(&self, buf: &mut McBuf) {
@@ -21,10 +21,21 @@ impl NetworkType for Uuid {
     }
 }
 
+impl NetworkType for u8 {
+    fn read(buf: &mut McBuf) -> BufferResult<Self> {
+        buf.read_ubyte()
+    }
+
+    fn write(&self, buf: &mut McBuf) {
+        buf.write_ubyte(*self)
+    }
+}
+
 impl <T> NetworkType for Vec<T> where T : NetworkType {
     fn read(buf: &mut McBuf) -> BufferResult<Self> {
-        let length = buf.read_var_int()?;
-        let mut vec = Vec::with_capacity(length as usize);
+        let length = usize::try_from(buf.read_var_int()?).map_err(|_| BufferError::NegativeLength)?;
+        // Every element takes at least one byte, so don't trust the length beyond that
+        let mut vec = Vec::with_capacity(length.min(buf.remaining()));
         for _ in 0..length {
             vec.push(buf.read_network_type()?);
         }
@@ -37,6 +48,14 @@ impl <T> NetworkType for Vec<T> where T : NetworkType {
             buf.write_network_type(item);
         }
     }
+
+    fn try_write(&self, buf: &mut McBuf) -> BufferResult<()> {
+        buf.write_var_int(self.len() as i32);
+        for item in self {
+            buf.try_write_network_type(item)?;
+        }
+        Ok(())
+    }
 }
 
 impl <T> NetworkType for Option<T> where T : NetworkType {
@@ -54,4 +73,12 @@ impl <T> NetworkType for Option<T> where T : NetworkType {
             buf.write_network_type(item);
         }
     }
+
+    fn try_write(&self, buf: &mut McBuf) -> BufferResult<()> {
+        buf.write_bool(self.is_some());
+        if let Some(item) = self {
+            buf.try_write_network_type(item)?;
+        }
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 pub mod extra;
+pub mod remaining_bytes;
 pub mod varint;
 
 pub trait NetworkSerializer<T> where T: Sized {
@@ -9,6 +10,14 @@ pub trait NetworkSerializer<T> where T: Sized {
 pub trait NetworkType where Self: Sized {
     fn read(buf: &mut McBuf) -> BufferResult<Self>;
     fn write(&self, buf: &mut McBuf);
+
+    /// Writes the value, failing instead if it cannot be encoded. Types whose
+    /// values are always valid keep the default, which never fails. On failure
+    /// the buffer may hold part of the value and should be discarded.
+    fn try_write(&self, buf: &mut McBuf) -> BufferResult<()> {
+        self.write(buf);
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -16,7 +25,14 @@ pub enum BufferError {
     BufferUnderflow,
     StringTooLong,
     StringError(std::string::FromUtf8Error),
+    InvalidModifiedUtf8,
+    NegativeLength,
+    DepthLimitExceeded,
+    SizeLimitExceeded,
+    ElementLimitExceeded,
     VarIntOverflow,
+    /// A value that cannot be encoded, with the reason.
+    Invalid(String),
 
     Generic(&'static str)
 }
@@ -30,6 +46,12 @@ pub struct McBuf {
     write_index: usize
 }
 
+impl Default for McBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Basic methods
 impl McBuf {
     pub fn new() -> Self {
@@ -72,6 +94,10 @@ impl McBuf {
     }
 
     pub fn read_dyn_array(&mut self, length: usize) -> BufferResult<Vec<u8>> {
+        if length > self.remaining() {
+            return Err(BufferError::BufferUnderflow);
+        }
+
         let start = self.read_index;
         let end = start + length;
         self.read_index = end;
@@ -82,9 +108,32 @@ impl McBuf {
         self.data.as_slice()
     }
 
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.data.as_mut_slice()
+    }
+
     pub fn length(&self) -> usize {
         self.data.len()
     }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.read_index
+    }
+
+    /// The bytes that have not been read yet.
+    pub fn unread(&self) -> &[u8] {
+        &self.data[self.read_index..]
+    }
+
+    /// Advances the read index past `length` bytes without copying them.
+    pub fn skip(&mut self, length: usize) -> BufferResult<()> {
+        if length > self.remaining() {
+            return Err(BufferError::BufferUnderflow);
+        }
+
+        self.read_index += length;
+        Ok(())
+    }
 }
 
 // Write methods
@@ -134,8 +183,6 @@ impl McBuf {
         self.write_slice(value.as_bytes())
     }
 
-    // TODO: Write TextComponent
-
     pub fn write_var_int(&mut self, value: i32) {
         self.data.reserve(5);
 
@@ -161,6 +208,10 @@ impl McBuf {
     pub fn write_network_type(&mut self, value: &impl NetworkType) {
         value.write(self);
     }
+
+    pub fn try_write_network_type(&mut self, value: &impl NetworkType) -> BufferResult<()> {
+        value.try_write(self)
+    }
 }
 
 // Read methods
@@ -212,7 +263,7 @@ impl McBuf {
         }
 
         let string = String::from_utf8(self.read_dyn_array(length)?)
-            .map_err(|e| BufferError::StringError(e))?;
+            .map_err(BufferError::StringError)?;
 
         if string.len() > max_length {
             return Err(BufferError::StringTooLong)
@@ -0,0 +1,23 @@
+use crate::{BufferResult, McBuf, NetworkType};
+
+/// Raw bytes running to the end of the packet, without a length prefix.
+/// Only valid as the last field of a packet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemainingBytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for RemainingBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl NetworkType for RemainingBytes {
+    fn read(buf: &mut McBuf) -> BufferResult<Self> {
+        let length = buf.remaining();
+        Ok(Self(buf.read_dyn_array(length)?))
+    }
+
+    fn write(&self, buf: &mut McBuf) {
+        buf.write_slice(&self.0);
+    }
+}
@@ -5,7 +5,7 @@ use leaflet_macros::{NetworkType, Packet};
 #[derive(Debug, NetworkType, Packet)]
 #[clientbound]
 #[state(Configuration)]
-#[packet_id(0x13)]
+#[packet_id(V1_21_9 = 0x13)]
 pub struct ClientboundCodeOfConductPacket {
     pub message: String
 }
\ No newline at end of file
@@ -0,0 +1,12 @@
+use crate::NetworkType;
+use crate::Packet;
+use leaflet_macros::{NetworkType, Packet};
+use leaflet_types::text_component::TextComponent;
+
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Configuration)]
+#[packet_id(2)]
+pub struct ClientboundConfigurationDisconnectPacket {
+    pub reason: TextComponent
+}
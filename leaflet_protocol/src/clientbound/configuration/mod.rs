@@ -3,4 +3,5 @@ pub mod registry_data;
 pub mod known_packs;
 pub mod update_tags;
 pub mod code_of_conduct;
-pub mod configuration_keep_alive;
\ No newline at end of file
+pub mod configuration_keep_alive;
+pub mod disconnect;
\ No newline at end of file
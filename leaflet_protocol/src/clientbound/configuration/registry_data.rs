@@ -29,3 +29,32 @@ impl RegistryEntry {
         Self { identifier, data: None }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use leaflet_nbt::NBTTag;
+    use leaflet_network_buffer::{BufferError, McBuf};
+    use leaflet_types::identifier::Identifier;
+    use crate::version::ProtocolVersion;
+    use crate::Packet;
+    use super::{ClientboundRegistryDataPacket, RegistryEntry};
+
+    fn packet(data: NBTTag) -> ClientboundRegistryDataPacket {
+        ClientboundRegistryDataPacket {
+            registry_id: Identifier::minecraft("dimension_type"),
+            entries: vec![RegistryEntry::new(Identifier::minecraft("overworld"), data)],
+        }
+    }
+
+    #[test]
+    fn invalid_entry_fails_the_packet() {
+        let mut buf = McBuf::new();
+        let valid = packet(NBTTag::compound(None, vec![NBTTag::int(Some("height".into()), 384)]));
+        assert!(valid.write_versioned(&mut buf, ProtocolVersion::LATEST).is_ok());
+
+        let mixed = NBTTag::list(Some("timelines".into()), vec![NBTTag::int(None, 1), NBTTag::string(None, "day".into())]);
+        let invalid = packet(NBTTag::compound(None, vec![mixed]));
+        let result = invalid.write_versioned(&mut McBuf::new(), ProtocolVersion::LATEST);
+        assert!(matches!(result, Err(BufferError::Invalid(_))));
+    }
+}
@@ -0,0 +1,12 @@
+use crate::NetworkType;
+use crate::Packet;
+use leaflet_macros::{NetworkType, Packet};
+use leaflet_types::text_component::TextComponent;
+
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Play)]
+#[packet_id(V1_21_7 = 0x1C, V1_21_9 = 0x20)]
+pub struct ClientboundDisconnectPacket {
+    pub reason: TextComponent
+}
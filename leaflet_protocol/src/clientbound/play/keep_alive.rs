@@ -5,7 +5,7 @@ use leaflet_macros::{NetworkType, Packet};
 #[derive(Debug, NetworkType, Packet)]
 #[clientbound]
 #[state(Play)]
-#[packet_id(0x2B)]
+#[packet_id(V1_21_7 = 0x26, V1_21_9 = 0x2B)]
 pub struct ClientboundKeepAlivePacket {
     pub id: i64
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ use leaflet_types::location::Location;
 #[derive(Debug, NetworkType, Packet)]
 #[clientbound]
 #[state(Play)]
-#[packet_id(0x30)]
+#[packet_id(V1_21_7 = 0x2B, V1_21_9 = 0x30)]
 pub struct ClientboundPlayLoginPacket {
     pub entity_id: i32,
     pub is_hardcore: bool,
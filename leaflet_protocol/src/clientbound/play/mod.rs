@@ -1,2 +1,3 @@
 pub mod login;
-pub mod keep_alive;
\ No newline at end of file
+pub mod keep_alive;
+pub mod disconnect;
\ No newline at end of file
@@ -0,0 +1,91 @@
+use aes::Aes128;
+use cfb8::cipher::{generic_array::GenericArray, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+
+/// The AES-128-CFB8 stream cipher used once a connection is encrypted.
+///
+/// Both sides use the shared secret as key and IV, and keep a separate cipher
+/// state per direction, so the same type works for servers and clients.
+pub struct PacketCipher {
+    encryptor: cfb8::Encryptor<Aes128>,
+    decryptor: cfb8::Decryptor<Aes128>,
+}
+
+impl PacketCipher {
+    pub fn new(shared_secret: &[u8; 16]) -> Self {
+        Self {
+            encryptor: cfb8::Encryptor::new(shared_secret.into(), shared_secret.into()),
+            decryptor: cfb8::Decryptor::new(shared_secret.into(), shared_secret.into()),
+        }
+    }
+
+    /// Encrypts outgoing bytes in place. Bytes must be passed in the order they are sent.
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.chunks_exact_mut(1) {
+            self.encryptor.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+
+    /// Decrypts incoming bytes in place. Bytes must be passed in the order they arrive.
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.chunks_exact_mut(1) {
+            self.decryptor.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aes::Aes128;
+    use cfb8::cipher::{AsyncStreamCipher, KeyIvInit};
+    use super::PacketCipher;
+
+    const KEY: [u8; 16] = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+    /// The CFB8 plaintext from NIST SP 800-38A, F.3.7.
+    const PLAINTEXT: [u8; 18] = [0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a, 0xae, 0x2d];
+
+    #[test]
+    fn cipher_matches_nist_vector() {
+        // F.3.7 uses a separate IV, so this checks the construction PacketCipher relies on
+        let iv: [u8; 16] = std::array::from_fn(|i| i as u8);
+        let mut data = PLAINTEXT;
+        cfb8::Encryptor::<Aes128>::new(&KEY.into(), &iv.into()).encrypt(&mut data);
+        assert_eq!(data, [0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f, 0x32, 0xb9]);
+    }
+
+    #[test]
+    fn uses_the_shared_secret_as_key_and_iv() {
+        // Computed with `openssl enc -aes-128-cfb8` with the key as IV
+        let expected = [0x14, 0x11, 0x8e, 0x07, 0x10, 0xeb, 0x1b, 0x4c, 0xa3, 0x0a, 0xe2, 0xde, 0x24, 0x74, 0x7e, 0xfb, 0x61, 0x73];
+        let mut data = PLAINTEXT;
+        PacketCipher::new(&KEY).encrypt(&mut data);
+        assert_eq!(data, expected);
+
+        PacketCipher::new(&KEY).decrypt(&mut data);
+        assert_eq!(data, PLAINTEXT);
+    }
+
+    #[test]
+    fn splitting_the_stream_does_not_change_it() {
+        let plaintext: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut whole = plaintext.clone();
+        PacketCipher::new(&KEY).encrypt(&mut whole);
+
+        let mut pieces = plaintext.clone();
+        let mut cipher = PacketCipher::new(&KEY);
+        let (mut rest, mut size) = (pieces.as_mut_slice(), 0);
+        while !rest.is_empty() {
+            let (piece, tail) = rest.split_at_mut(size.min(rest.len()));
+            cipher.encrypt(piece);
+            (rest, size) = (tail, size + 7);
+        }
+        assert_eq!(pieces, whole);
+
+        // Each direction keeps its own state, so encrypting does not disturb decrypting
+        let mut cipher = PacketCipher::new(&KEY);
+        let (head, tail) = whole.split_at_mut(333);
+        cipher.decrypt(head);
+        cipher.encrypt(&mut [0; 16]);
+        cipher.decrypt(tail);
+        assert_eq!(whole, plaintext);
+    }
+}
@@ -3,20 +3,23 @@ extern crate leaflet_macros;
 
 use std::fmt::Debug;
 use leaflet_macros::packet_handlers;
-use leaflet_network_buffer::NetworkType;
-use login::{ClientboundLoginSuccessPacket, ServerboundLoginAcknowledgedPacket, ServerboundLoginStartPacket};
+use leaflet_network_buffer::{BufferResult, McBuf, NetworkType};
+use login::{ClientboundEncryptionRequestPacket, ClientboundLoginDisconnectPacket, ClientboundLoginPluginRequestPacket, ClientboundLoginSuccessPacket, ClientboundSetCompressionPacket, ServerboundEncryptionResponsePacket, ServerboundLoginAcknowledgedPacket, ServerboundLoginPluginResponsePacket, ServerboundLoginStartPacket};
 use serverbound::configuration::accept_code_of_conduct::ServerboundAcceptCodeOfConductPacket;
 use serverbound::configuration::acknowledge_finish_configuration::ServerboundAcknowledgeFinishConfigurationPacket;
 use serverbound::configuration::client_information::ServerboundClientInformationPacket;
 use serverbound::configuration::configuration_keep_alive_response::ServerboundConfigurationKeepAliveResponsePacket;
 use serverbound::configuration::known_packs::ServerboundKnownPacksPacket;
 use serverbound::handshake::serverbound_handshake::ServerboundHandshakePacket;
+use version::ProtocolVersion;
 use status::{ClientboundPongPacket, ClientboundStatusResponsePacket, ServerboundPingPacket, ServerboundStatusRequestPacket};
 
+pub mod encryption;
 pub mod login;
 pub mod status;
 pub mod clientbound;
 pub mod serverbound;
+pub mod version;
 
 pub enum ConnectionState {
     Handshake,
@@ -34,7 +37,22 @@ pub enum PacketDirection {
 pub trait Packet: Sized + NetworkType + Debug {
     const STATE: ConnectionState;
     const DIRECTION: PacketDirection;
-    const ID: i32;
+
+    /// The packet's id in `version`, or `None` if that version does not have it.
+    fn id(version: ProtocolVersion) -> Option<i32>;
+
+    /// Reads the packet as laid out in `version`. The derive always uses the
+    /// [`NetworkType`] layout, so a packet whose fields differ between versions
+    /// implements this trait by hand.
+    fn read_versioned(buf: &mut McBuf, _version: ProtocolVersion) -> BufferResult<Self> {
+        Self::read(buf)
+    }
+
+    /// Writes the packet as laid out in `version`, failing if a field cannot be
+    /// encoded. See [`NetworkType::try_write`].
+    fn write_versioned(&self, buf: &mut McBuf, _version: ProtocolVersion) -> BufferResult<()> {
+        self.try_write(buf)
+    }
 }
 
 packet_handlers! {
@@ -49,8 +67,8 @@ packet_handlers! {
   }
 
   Login {
-    serverbound: [ServerboundLoginStartPacket, ServerboundLoginAcknowledgedPacket],
-    clientbound: [ClientboundLoginSuccessPacket],
+    serverbound: [ServerboundLoginStartPacket, ServerboundEncryptionResponsePacket, ServerboundLoginPluginResponsePacket, ServerboundLoginAcknowledgedPacket],
+    clientbound: [ClientboundLoginDisconnectPacket, ClientboundEncryptionRequestPacket, ClientboundLoginSuccessPacket, ClientboundSetCompressionPacket, ClientboundLoginPluginRequestPacket],
   }
 
   Configuration {
@@ -69,3 +87,36 @@ packet_handlers! {
     clientbound: [],
   }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::clientbound::configuration::code_of_conduct::ClientboundCodeOfConductPacket;
+    use crate::clientbound::configuration::registry_data::ClientboundRegistryDataPacket;
+    use crate::clientbound::play::keep_alive::ClientboundKeepAlivePacket;
+    use crate::clientbound::play::login::ClientboundPlayLoginPacket;
+    use crate::version::ProtocolVersion::{V1_21_11, V1_21_7, V1_21_9};
+    use crate::Packet;
+
+    #[test]
+    fn picks_the_id_of_the_newest_version_listed() {
+        assert_eq!(ClientboundPlayLoginPacket::id(V1_21_7), Some(0x2B));
+        assert_eq!(ClientboundPlayLoginPacket::id(V1_21_9), Some(0x30));
+        assert_eq!(ClientboundPlayLoginPacket::id(V1_21_11), Some(0x30));
+        assert_eq!(ClientboundKeepAlivePacket::id(V1_21_7), Some(0x26));
+        assert_eq!(ClientboundKeepAlivePacket::id(V1_21_9), Some(0x2B));
+    }
+
+    #[test]
+    fn packets_are_missing_before_their_first_version() {
+        assert_eq!(ClientboundCodeOfConductPacket::id(V1_21_7), None);
+        assert_eq!(ClientboundCodeOfConductPacket::id(V1_21_9), Some(0x13));
+        assert_eq!(ClientboundCodeOfConductPacket::id(V1_21_11), Some(0x13));
+    }
+
+    #[test]
+    fn single_ids_apply_to_every_version() {
+        for version in [V1_21_7, V1_21_9, V1_21_11] {
+            assert_eq!(ClientboundRegistryDataPacket::id(version), Some(7));
+        }
+    }
+}
@@ -2,7 +2,9 @@ use crate::NetworkType;
 use uuid::Uuid;
 use crate::Packet;
 use leaflet_macros::{NetworkType, Packet};
+use leaflet_network_buffer::remaining_bytes::RemainingBytes;
 use leaflet_types::game_profile::GameProfile;
+use leaflet_types::identifier::Identifier;
 
 #[derive(Debug, NetworkType, Packet)]
 #[serverbound]
@@ -12,6 +14,29 @@ pub struct ServerboundLoginStartPacket {
     pub username: String,
     pub uuid: Uuid
 }
+#[derive(Debug, NetworkType, Packet)]
+#[serverbound]
+#[state(Login)]
+#[packet_id(1)]
+pub struct ServerboundEncryptionResponsePacket {
+    /// The shared secret, encrypted with the server's public key.
+    pub shared_secret: Vec<u8>,
+    /// The verify token from the request, encrypted with the server's public key.
+    pub verify_token: Vec<u8>
+}
+
+#[derive(Debug, NetworkType, Packet)]
+#[serverbound]
+#[state(Login)]
+#[packet_id(2)]
+pub struct ServerboundLoginPluginResponsePacket {
+    #[varint]
+    pub message_id: i32,
+    /// Whether the client understood the request. `data` is empty if not.
+    pub successful: bool,
+    pub data: RemainingBytes
+}
+
 #[derive(Debug, NetworkType, Packet)]
 #[serverbound]
 #[state(Login)]
@@ -20,6 +45,29 @@ pub struct ServerboundLoginAcknowledgedPacket;
 
 
 
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Login)]
+#[packet_id(0)]
+pub struct ClientboundLoginDisconnectPacket {
+    /// The reason as a JSON text component.
+    pub reason: String
+}
+
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Login)]
+#[packet_id(1)]
+pub struct ClientboundEncryptionRequestPacket {
+    /// Always empty since 1.7.
+    pub server_id: String,
+    /// The server's RSA public key, DER encoded.
+    pub public_key: Vec<u8>,
+    pub verify_token: Vec<u8>,
+    /// Whether the client should authenticate with the session server.
+    pub should_authenticate: bool
+}
+
 #[derive(Debug, NetworkType, Packet)]
 #[clientbound]
 #[state(Login)]
@@ -27,3 +75,24 @@ pub struct ServerboundLoginAcknowledgedPacket;
 pub struct ClientboundLoginSuccessPacket {
     pub gameprofile: GameProfile
 }
+
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Login)]
+#[packet_id(3)]
+pub struct ClientboundSetCompressionPacket {
+    #[varint]
+    pub threshold: i32
+}
+
+#[derive(Debug, NetworkType, Packet)]
+#[clientbound]
+#[state(Login)]
+#[packet_id(4)]
+pub struct ClientboundLoginPluginRequestPacket {
+    /// Chosen by the server, and echoed back in the response.
+    #[varint]
+    pub message_id: i32,
+    pub channel: Identifier,
+    pub data: RemainingBytes
+}
@@ -5,5 +5,5 @@ use leaflet_macros::{NetworkType, Packet};
 #[derive(Debug, NetworkType, Packet)]
 #[serverbound]
 #[state(Configuration)]
-#[packet_id(9)]
+#[packet_id(V1_21_9 = 9)]
 pub struct ServerboundAcceptCodeOfConductPacket;
@@ -0,0 +1,60 @@
+/// A protocol version the server can speak, oldest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolVersion {
+    /// 1.21.7 and 1.21.8
+    V1_21_7,
+    /// 1.21.9 and 1.21.10
+    V1_21_9,
+    /// 1.21.11
+    V1_21_11,
+}
+
+impl ProtocolVersion {
+    /// All supported versions, oldest first.
+    pub const ALL: &'static [ProtocolVersion] = &[
+        ProtocolVersion::V1_21_7,
+        ProtocolVersion::V1_21_9,
+        ProtocolVersion::V1_21_11,
+    ];
+    pub const OLDEST: ProtocolVersion = ProtocolVersion::ALL[0];
+    pub const LATEST: ProtocolVersion = ProtocolVersion::ALL[ProtocolVersion::ALL.len() - 1];
+
+    /// The number sent in the handshake.
+    pub fn protocol(self) -> i32 {
+        match self {
+            ProtocolVersion::V1_21_7 => 772,
+            ProtocolVersion::V1_21_9 => 773,
+            ProtocolVersion::V1_21_11 => 774,
+        }
+    }
+
+    /// The releases speaking this version, oldest first.
+    pub fn releases(self) -> &'static [&'static str] {
+        match self {
+            ProtocolVersion::V1_21_7 => &["1.21.7", "1.21.8"],
+            ProtocolVersion::V1_21_9 => &["1.21.9", "1.21.10"],
+            ProtocolVersion::V1_21_11 => &["1.21.11"],
+        }
+    }
+
+    /// The first release speaking this version, like `1.21.9`.
+    pub fn name(self) -> &'static str {
+        self.releases()[0]
+    }
+
+    /// The supported version with this number, if any.
+    pub fn from_protocol(protocol: i32) -> Option<ProtocolVersion> {
+        ProtocolVersion::ALL.iter().copied().find(|version| version.protocol() == protocol)
+    }
+
+    /// The supported releases as shown to players, like `1.21.7-1.21.11`.
+    pub fn supported_range() -> String {
+        let oldest = ProtocolVersion::OLDEST.name();
+        let latest = ProtocolVersion::LATEST.releases().last().copied().unwrap_or(oldest);
+        if oldest == latest {
+            oldest.to_string()
+        } else {
+            format!("{oldest}-{latest}")
+        }
+    }
+}
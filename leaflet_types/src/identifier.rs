@@ -1,7 +1,7 @@
 use std::fmt::Display;
 use leaflet_network_buffer::{BufferResult, McBuf, NetworkType};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Identifier {
     namespace: String,
     value: String,
@@ -18,18 +18,25 @@ impl Identifier {
     pub fn minecraft(value: &str) -> Self {
         Self::new("minecraft", value)
     }
+
+    /// Parses `namespace:value`, where the namespace defaults to `minecraft`.
+    pub fn parse(identifier: &str) -> Self {
+        match identifier.split_once(':') {
+            Some((namespace, value)) => Self::new(namespace, value),
+            None => Self::minecraft(identifier),
+        }
+    }
 }
 
 impl Display for Identifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", format!("{}:{}", self.namespace, self.value))
+        write!(f, "{}:{}", self.namespace, self.value)
     }
 }
 
 impl NetworkType for Identifier {
     fn read(buf: &mut McBuf) -> BufferResult<Self> {
-        buf.read_string(32767)
-            .map(|s| Self::new(&s[..s.find(':').unwrap()], &s[s.find(':').unwrap() + 1..]))
+        buf.read_string(32767).map(|s| Self::parse(&s))
     }
 
     fn write(&self, buf: &mut McBuf) {
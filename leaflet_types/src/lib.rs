@@ -1,3 +1,5 @@
 pub mod game_profile;
 pub mod identifier;
 pub mod location;
+pub mod server_status;
+pub mod text_component;
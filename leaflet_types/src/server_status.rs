@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::text_component::TextComponent;
+
+/// The Status Response shown in the server list, serialized to JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub version: StatusVersion,
+    /// Left out to show `???` instead of a player count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub players: Option<StatusPlayers>,
+    /// The MOTD.
+    pub description: TextComponent,
+    /// A 64x64 PNG as a `data:image/png;base64,` URI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    #[serde(default)]
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusPlayers {
+    pub max: i32,
+    pub online: i32,
+    /// Shown when hovering the player count.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sample: Vec<StatusPlayer>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusPlayer {
+    pub name: String,
+    pub id: Uuid,
+}
+
+impl ServerStatus {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("status serializes to JSON")
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt::{Display, Formatter};
+
+/// The sixteen colors with a name and a legacy formatting code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor {
+    /// All colors, ordered by their legacy code.
+    pub const ALL: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::DarkBlue,
+        NamedColor::DarkGreen,
+        NamedColor::DarkAqua,
+        NamedColor::DarkRed,
+        NamedColor::DarkPurple,
+        NamedColor::Gold,
+        NamedColor::Gray,
+        NamedColor::DarkGray,
+        NamedColor::Blue,
+        NamedColor::Green,
+        NamedColor::Aqua,
+        NamedColor::Red,
+        NamedColor::LightPurple,
+        NamedColor::Yellow,
+        NamedColor::White,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NamedColor::Black => "black",
+            NamedColor::DarkBlue => "dark_blue",
+            NamedColor::DarkGreen => "dark_green",
+            NamedColor::DarkAqua => "dark_aqua",
+            NamedColor::DarkRed => "dark_red",
+            NamedColor::DarkPurple => "dark_purple",
+            NamedColor::Gold => "gold",
+            NamedColor::Gray => "gray",
+            NamedColor::DarkGray => "dark_gray",
+            NamedColor::Blue => "blue",
+            NamedColor::Green => "green",
+            NamedColor::Aqua => "aqua",
+            NamedColor::Red => "red",
+            NamedColor::LightPurple => "light_purple",
+            NamedColor::Yellow => "yellow",
+            NamedColor::White => "white",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NamedColor> {
+        NamedColor::ALL.into_iter().find(|color| color.name() == name)
+    }
+
+    /// The character following `§` in legacy formatting codes.
+    pub fn code(self) -> char {
+        char::from_digit(self as u32, 16).unwrap()
+    }
+
+    pub fn from_code(code: char) -> Option<NamedColor> {
+        code.to_digit(16).map(|index| NamedColor::ALL[index as usize])
+    }
+
+    pub fn rgb(self) -> u32 {
+        match self {
+            NamedColor::Black => 0x000000,
+            NamedColor::DarkBlue => 0x0000AA,
+            NamedColor::DarkGreen => 0x00AA00,
+            NamedColor::DarkAqua => 0x00AAAA,
+            NamedColor::DarkRed => 0xAA0000,
+            NamedColor::DarkPurple => 0xAA00AA,
+            NamedColor::Gold => 0xFFAA00,
+            NamedColor::Gray => 0xAAAAAA,
+            NamedColor::DarkGray => 0x555555,
+            NamedColor::Blue => 0x5555FF,
+            NamedColor::Green => 0x55FF55,
+            NamedColor::Aqua => 0x55FFFF,
+            NamedColor::Red => 0xFF5555,
+            NamedColor::LightPurple => 0xFF55FF,
+            NamedColor::Yellow => 0xFFFF55,
+            NamedColor::White => 0xFFFFFF,
+        }
+    }
+}
+
+/// A text color, either named or any RGB value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextColor {
+    Named(NamedColor),
+    Rgb(u32),
+}
+
+impl TextColor {
+    /// Parses a color name or `#RRGGBB`.
+    pub fn parse(color: &str) -> Option<TextColor> {
+        match color.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16).ok().map(TextColor::Rgb),
+            Some(_) => None,
+            None => NamedColor::from_name(color).map(TextColor::Named),
+        }
+    }
+
+    pub fn rgb(self) -> u32 {
+        match self {
+            TextColor::Named(color) => color.rgb(),
+            TextColor::Rgb(rgb) => rgb,
+        }
+    }
+}
+
+impl From<NamedColor> for TextColor {
+    fn from(color: NamedColor) -> Self {
+        TextColor::Named(color)
+    }
+}
+
+/// The name, or `#RRGGBB` for other colors.
+impl Display for TextColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextColor::Named(color) => f.write_str(color.name()),
+            TextColor::Rgb(rgb) => write!(f, "#{rgb:06X}"),
+        }
+    }
+}
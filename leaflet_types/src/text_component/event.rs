@@ -0,0 +1,63 @@
+use leaflet_nbt::{NbtCompound, TagContent};
+use uuid::Uuid;
+use crate::identifier::Identifier;
+use crate::text_component::TextComponent;
+
+/// What happens when a component is clicked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClickEvent {
+    OpenUrl(String),
+    /// Only used by the client itself, servers cannot send it.
+    OpenFile(String),
+    RunCommand(String),
+    SuggestCommand(String),
+    /// Turns to a page in a book.
+    ChangePage(i32),
+    CopyToClipboard(String),
+    /// Opens a dialog, given by its id or inline.
+    ShowDialog(TagContent),
+    /// Sends a Custom Click Action packet back to the server.
+    Custom { id: Identifier, payload: Option<TagContent> },
+}
+
+impl ClickEvent {
+    pub fn action(&self) -> &'static str {
+        match self {
+            ClickEvent::OpenUrl(_) => "open_url",
+            ClickEvent::OpenFile(_) => "open_file",
+            ClickEvent::RunCommand(_) => "run_command",
+            ClickEvent::SuggestCommand(_) => "suggest_command",
+            ClickEvent::ChangePage(_) => "change_page",
+            ClickEvent::CopyToClipboard(_) => "copy_to_clipboard",
+            ClickEvent::ShowDialog(_) => "show_dialog",
+            ClickEvent::Custom { .. } => "custom",
+        }
+    }
+}
+
+/// What is shown when hovering over a component.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+    /// An item tooltip, with its data components.
+    ShowItem {
+        id: Identifier,
+        count: i32,
+        components: Option<NbtCompound>,
+    },
+    ShowEntity {
+        entity_type: Identifier,
+        uuid: Uuid,
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+impl HoverEvent {
+    pub fn action(&self) -> &'static str {
+        match self {
+            HoverEvent::ShowText(_) => "show_text",
+            HoverEvent::ShowItem { .. } => "show_item",
+            HoverEvent::ShowEntity { .. } => "show_entity",
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use serde_json::{Map, Value};
+use leaflet_nbt::{NbtCompound, TagContent};
+use uuid::Uuid;
+use crate::identifier::Identifier;
+use crate::text_component::{
+    ClickEvent, Content, HoverEvent, NbtSource, Style, TextColor, TextComponent, TextComponentError,
+    TextComponentResult,
+};
+
+fn error<T>(message: impl Into<String>) -> TextComponentResult<T> {
+    Err(TextComponentError(message.into()))
+}
+
+fn get_string(object: &Map<String, Value>, key: &str) -> TextComponentResult<Option<String>> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => error(format!("`{key}` must be a string")),
+    }
+}
+
+fn require_string(object: &Map<String, Value>, key: &str) -> TextComponentResult<String> {
+    get_string(object, key)?.map_or_else(|| error(format!("missing `{key}`")), Ok)
+}
+
+/// Reads a boolean, which NBT stores as a byte.
+fn get_bool(object: &Map<String, Value>, key: &str) -> TextComponentResult<Option<bool>> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(Value::Bool(value)) => Ok(Some(*value)),
+        Some(Value::Number(value)) => Ok(Some(value.as_f64() != Some(0.0))),
+        Some(_) => error(format!("`{key}` must be a boolean")),
+    }
+}
+
+fn get_int(object: &Map<String, Value>, key: &str) -> TextComponentResult<Option<i32>> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(Value::Number(value)) => match value.as_i64().and_then(|value| i32::try_from(value).ok()) {
+            Some(value) => Ok(Some(value)),
+            None => error(format!("`{key}` must be an int")),
+        },
+        Some(_) => error(format!("`{key}` must be an int")),
+    }
+}
+
+fn get_component(object: &Map<String, Value>, key: &str) -> TextComponentResult<Option<Box<TextComponent>>> {
+    object.get(key).map(|value| TextComponent::from_json(value).map(Box::new)).transpose()
+}
+
+fn get_compound(object: &Map<String, Value>, key: &str) -> TextComponentResult<Option<NbtCompound>> {
+    match object.get(key).map(TagContent::from_json) {
+        None => Ok(None),
+        Some(TagContent::Compound(compound)) => Ok(Some(compound)),
+        Some(_) => error(format!("`{key}` must be an object")),
+    }
+}
+
+/// Reads a UUID as a string or as four ints.
+fn read_uuid(value: &Value) -> TextComponentResult<Uuid> {
+    match value {
+        Value::String(uuid) => Uuid::parse_str(uuid).or_else(|_| error(format!("invalid UUID `{uuid}`"))),
+        Value::Array(parts) if parts.len() == 4 => {
+            let mut bytes = [0u8; 16];
+            for (chunk, part) in bytes.chunks_exact_mut(4).zip(parts) {
+                let part = part.as_i64().and_then(|part| i32::try_from(part).ok());
+                let Some(part) = part else {
+                    return error("UUID parts must be ints");
+                };
+                chunk.copy_from_slice(&part.to_be_bytes());
+            }
+            Ok(Uuid::from_bytes(bytes))
+        }
+        _ => error("a UUID must be a string or four ints"),
+    }
+}
+
+/// Reads a shadow color as an ARGB int or as four floats from 0 to 1.
+fn read_shadow_color(value: &Value) -> TextComponentResult<u32> {
+    match value {
+        Value::Number(argb) => match argb.as_i64() {
+            Some(argb) if i32::try_from(argb).is_ok() || u32::try_from(argb).is_ok() => Ok(argb as u32),
+            _ => error("`shadow_color` is out of range"),
+        },
+        Value::Array(channels) if channels.len() == 4 => {
+            let mut rgba = 0u32;
+            for channel in channels {
+                let Some(channel) = channel.as_f64() else {
+                    return error("`shadow_color` channels must be numbers");
+                };
+                rgba = (rgba << 8) | (channel.clamp(0.0, 1.0) * 255.0).round() as u32;
+            }
+            Ok(rgba.rotate_right(8))
+        }
+        _ => error("`shadow_color` must be an int or four floats"),
+    }
+}
+
+fn content_to_json(content: &Content, object: &mut Map<String, Value>) {
+    match content {
+        Content::Text(text) => {
+            object.insert("text".into(), text.clone().into());
+        }
+        Content::Translatable { key, fallback, args } => {
+            object.insert("translate".into(), key.clone().into());
+            if let Some(fallback) = fallback {
+                object.insert("fallback".into(), fallback.clone().into());
+            }
+            if !args.is_empty() {
+                object.insert("with".into(), args.iter().map(TextComponent::to_json).collect());
+            }
+        }
+        Content::Score { name, objective } => {
+            object.insert("score".into(), serde_json::json!({ "name": name, "objective": objective }));
+        }
+        Content::Selector { selector, separator } => {
+            object.insert("selector".into(), selector.clone().into());
+            if let Some(separator) = separator {
+                object.insert("separator".into(), separator.to_json());
+            }
+        }
+        Content::Keybind(key) => {
+            object.insert("keybind".into(), key.clone().into());
+        }
+        Content::Nbt { path, interpret, separator, source } => {
+            object.insert("nbt".into(), path.clone().into());
+            if *interpret {
+                object.insert("interpret".into(), true.into());
+            }
+            if let Some(separator) = separator {
+                object.insert("separator".into(), separator.to_json());
+            }
+            let (key, value) = match source {
+                NbtSource::Block(position) => ("block", position.clone()),
+                NbtSource::Entity(selector) => ("entity", selector.clone()),
+                NbtSource::Storage(storage) => ("storage", storage.to_string()),
+            };
+            object.insert(key.into(), value.into());
+        }
+    }
+}
+
+fn content_from_json(object: &Map<String, Value>) -> TextComponentResult<Content> {
+    const TYPES: [(&str, &str); 6] = [
+        ("text", "text"),
+        ("translatable", "translate"),
+        ("score", "score"),
+        ("selector", "selector"),
+        ("keybind", "keybind"),
+        ("nbt", "nbt"),
+    ];
+
+    // The type is optional, otherwise the first known key decides
+    let kind = match get_string(object, "type")? {
+        Some(kind) => match TYPES.iter().find(|(name, _)| *name == kind) {
+            Some((name, _)) => *name,
+            None => return error(format!("unknown type `{kind}`")),
+        },
+        None => match TYPES.iter().find(|(_, key)| object.contains_key(*key)) {
+            Some((name, _)) => *name,
+            None => return error("no content"),
+        },
+    };
+
+    Ok(match kind {
+        "text" => Content::Text(require_string(object, "text")?),
+        "translatable" => Content::Translatable {
+            key: require_string(object, "translate")?,
+            fallback: get_string(object, "fallback")?,
+            args: match object.get("with") {
+                None => Vec::new(),
+                Some(Value::Array(args)) => args.iter().map(argument_from_json).collect::<TextComponentResult<_>>()?,
+                Some(_) => return error("`with` must be a list"),
+            },
+        },
+        "score" => match object.get("score") {
+            Some(Value::Object(score)) => Content::Score {
+                name: require_string(score, "name")?,
+                objective: require_string(score, "objective")?,
+            },
+            _ => return error("`score` must be an object"),
+        },
+        "selector" => Content::Selector {
+            selector: require_string(object, "selector")?,
+            separator: get_component(object, "separator")?,
+        },
+        "keybind" => Content::Keybind(require_string(object, "keybind")?),
+        _ => Content::Nbt {
+            path: require_string(object, "nbt")?,
+            interpret: get_bool(object, "interpret")?.unwrap_or(false),
+            separator: get_component(object, "separator")?,
+            source: if let Some(position) = get_string(object, "block")? {
+                NbtSource::Block(position)
+            } else if let Some(selector) = get_string(object, "entity")? {
+                NbtSource::Entity(selector)
+            } else if let Some(storage) = get_string(object, "storage")? {
+                NbtSource::Storage(Identifier::parse(&storage))
+            } else {
+                return error("`nbt` needs a `block`, `entity` or `storage` source");
+            },
+        },
+    })
+}
+
+/// Translation arguments may also be plain numbers and booleans.
+fn argument_from_json(value: &Value) -> TextComponentResult<TextComponent> {
+    match value {
+        Value::Number(number) => Ok(TextComponent::text(number.to_string())),
+        Value::Bool(value) => Ok(TextComponent::text(value.to_string())),
+        value => TextComponent::from_json(value),
+    }
+}
+
+fn click_event_to_json(event: &ClickEvent) -> Value {
+    let (key, value) = match event {
+        ClickEvent::OpenUrl(url) => ("url", Value::from(url.clone())),
+        ClickEvent::OpenFile(path) => ("path", path.clone().into()),
+        ClickEvent::RunCommand(command) | ClickEvent::SuggestCommand(command) => ("command", command.clone().into()),
+        ClickEvent::ChangePage(page) => ("page", (*page).into()),
+        ClickEvent::CopyToClipboard(value) => ("value", value.clone().into()),
+        ClickEvent::ShowDialog(dialog) => ("dialog", dialog.to_json()),
+        ClickEvent::Custom { id, payload } => {
+            let mut object = serde_json::json!({ "action": event.action(), "id": id.to_string() });
+            if let Some(payload) = payload {
+                object["payload"] = payload.to_json();
+            }
+            return object;
+        }
+    };
+    serde_json::json!({ "action": event.action(), key: value })
+}
+
+fn click_event_from_json(value: &Value) -> TextComponentResult<ClickEvent> {
+    let Value::Object(object) = value else {
+        return error("`click_event` must be an object");
+    };
+    Ok(match require_string(object, "action")?.as_str() {
+        "open_url" => ClickEvent::OpenUrl(require_string(object, "url")?),
+        "open_file" => ClickEvent::OpenFile(require_string(object, "path")?),
+        "run_command" => ClickEvent::RunCommand(require_string(object, "command")?),
+        "suggest_command" => ClickEvent::SuggestCommand(require_string(object, "command")?),
+        "change_page" => ClickEvent::ChangePage(get_int(object, "page")?.map_or_else(|| error("missing `page`"), Ok)?),
+        "copy_to_clipboard" => ClickEvent::CopyToClipboard(require_string(object, "value")?),
+        "show_dialog" => match object.get("dialog") {
+            Some(dialog) => ClickEvent::ShowDialog(TagContent::from_json(dialog)),
+            None => return error("missing `dialog`"),
+        },
+        "custom" => ClickEvent::Custom {
+            id: Identifier::parse(&require_string(object, "id")?),
+            payload: object.get("payload").map(TagContent::from_json),
+        },
+        action => return error(format!("unknown click action `{action}`")),
+    })
+}
+
+fn hover_event_to_json(event: &HoverEvent) -> Value {
+    let mut object = Map::new();
+    object.insert("action".into(), event.action().into());
+    match event {
+        HoverEvent::ShowText(text) => {
+            object.insert("value".into(), text.to_json());
+        }
+        HoverEvent::ShowItem { id, count, components } => {
+            object.insert("id".into(), id.to_string().into());
+            if *count != 1 {
+                object.insert("count".into(), (*count).into());
+            }
+            if let Some(components) = components {
+                object.insert("components".into(), TagContent::Compound(components.clone()).to_json());
+            }
+        }
+        HoverEvent::ShowEntity { entity_type, uuid, name } => {
+            object.insert("id".into(), entity_type.to_string().into());
+            // As a string, since four ints would not survive the conversion to NBT as an int array
+            object.insert("uuid".into(), uuid.to_string().into());
+            if let Some(name) = name {
+                object.insert("name".into(), name.to_json());
+            }
+        }
+    }
+    Value::Object(object)
+}
+
+fn hover_event_from_json(value: &Value) -> TextComponentResult<HoverEvent> {
+    let Value::Object(object) = value else {
+        return error("`hover_event` must be an object");
+    };
+    Ok(match require_string(object, "action")?.as_str() {
+        "show_text" => match get_component(object, "value")? {
+            Some(text) => HoverEvent::ShowText(text),
+            None => return error("missing `value`"),
+        },
+        "show_item" => HoverEvent::ShowItem {
+            id: Identifier::parse(&require_string(object, "id")?),
+            count: get_int(object, "count")?.unwrap_or(1),
+            components: get_compound(object, "components")?,
+        },
+        "show_entity" => HoverEvent::ShowEntity {
+            entity_type: Identifier::parse(&require_string(object, "id")?),
+            uuid: match object.get("uuid") {
+                Some(uuid) => read_uuid(uuid)?,
+                None => return error("missing `uuid`"),
+            },
+            name: get_component(object, "name")?,
+        },
+        action => return error(format!("unknown hover action `{action}`")),
+    })
+}
+
+fn style_to_json(style: &Style, object: &mut Map<String, Value>) {
+    if let Some(color) = style.color {
+        object.insert("color".into(), color.to_string().into());
+    }
+    if let Some(argb) = style.shadow_color {
+        object.insert("shadow_color".into(), (argb as i32).into());
+    }
+    if let Some(font) = &style.font {
+        object.insert("font".into(), font.to_string().into());
+    }
+    let flags = [
+        ("bold", style.bold),
+        ("italic", style.italic),
+        ("underlined", style.underlined),
+        ("strikethrough", style.strikethrough),
+        ("obfuscated", style.obfuscated),
+    ];
+    for (key, value) in flags {
+        if let Some(value) = value {
+            object.insert(key.into(), value.into());
+        }
+    }
+    if let Some(insertion) = &style.insertion {
+        object.insert("insertion".into(), insertion.clone().into());
+    }
+    if let Some(event) = &style.click_event {
+        object.insert("click_event".into(), click_event_to_json(event));
+    }
+    if let Some(event) = &style.hover_event {
+        object.insert("hover_event".into(), hover_event_to_json(event));
+    }
+}
+
+fn style_from_json(object: &Map<String, Value>) -> TextComponentResult<Style> {
+    Ok(Style {
+        color: match get_string(object, "color")? {
+            Some(color) => match TextColor::parse(&color) {
+                Some(color) => Some(color),
+                None => return error(format!("unknown color `{color}`")),
+            },
+            None => None,
+        },
+        shadow_color: object.get("shadow_color").map(read_shadow_color).transpose()?,
+        font: get_string(object, "font")?.map(|font| Identifier::parse(&font)),
+        bold: get_bool(object, "bold")?,
+        italic: get_bool(object, "italic")?,
+        underlined: get_bool(object, "underlined")?,
+        strikethrough: get_bool(object, "strikethrough")?,
+        obfuscated: get_bool(object, "obfuscated")?,
+        insertion: get_string(object, "insertion")?,
+        click_event: object.get("click_event").map(click_event_from_json).transpose()?,
+        hover_event: object.get("hover_event").map(hover_event_from_json).transpose()?,
+    })
+}
+
+impl TextComponent {
+    /// Encodes the component as JSON. Unstyled text without children becomes a
+    /// plain string.
+    pub fn to_json(&self) -> Value {
+        if let Content::Text(text) = &self.content
+            && self.style.is_empty()
+            && self.children.is_empty()
+        {
+            return Value::String(text.clone());
+        }
+
+        let mut object = Map::new();
+        content_to_json(&self.content, &mut object);
+        style_to_json(&self.style, &mut object);
+        if !self.children.is_empty() {
+            object.insert("extra".into(), self.children.iter().map(TextComponent::to_json).collect());
+        }
+        Value::Object(object)
+    }
+
+    /// Reads a component from a string, an object, or a list whose first element
+    /// is the parent of the others.
+    pub fn from_json(value: &Value) -> TextComponentResult<TextComponent> {
+        match value {
+            Value::String(text) => Ok(TextComponent::text(text.clone())),
+            Value::Array(components) => {
+                let Some((first, rest)) = components.split_first() else {
+                    return error("empty list");
+                };
+                let mut component = TextComponent::from_json(first)?;
+                for child in rest {
+                    component.children.push(TextComponent::from_json(child)?);
+                }
+                Ok(component)
+            }
+            Value::Object(object) => Ok(TextComponent {
+                content: content_from_json(object)?,
+                style: style_from_json(object)?,
+                children: match object.get("extra") {
+                    None => Vec::new(),
+                    Some(Value::Array(children)) if !children.is_empty() => {
+                        children.iter().map(TextComponent::from_json).collect::<TextComponentResult<_>>()?
+                    }
+                    Some(_) => return error("`extra` must be a non-empty list"),
+                },
+            }),
+            _ => error("expected a string, list or object"),
+        }
+    }
+}
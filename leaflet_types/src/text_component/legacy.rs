@@ -0,0 +1,209 @@
+//! Legacy formatting codes like `§cHello §lworld`, or `&c` as typed in config
+//! files. A color code resets the formatting codes before it, `r` resets
+//! everything, and RGB colors are written `§#RRGGBB` or `§x§R§R§G§G§B§B`.
+
+use crate::text_component::{Content, NamedColor, Style, TextColor, TextComponent};
+
+/// The prefix used by the game itself.
+pub const SECTION_SIGN: char = '§';
+/// The prefix usually typed by people, since `§` is hard to type.
+pub const AMPERSAND: char = '&';
+
+/// Parses `#RRGGBB` or `x` followed by six prefixed hex digits, returning the
+/// color and the number of chars used.
+fn parse_rgb(chars: &[char], prefix: char) -> Option<(u32, usize)> {
+    let hex: String = match chars.first()? {
+        '#' => chars.get(1..7)?.iter().collect(),
+        'x' | 'X' => {
+            let digits = chars.get(1..13)?;
+            if digits.iter().step_by(2).any(|c| *c != prefix) {
+                return None;
+            }
+            digits.iter().skip(1).step_by(2).collect()
+        }
+        _ => return None,
+    };
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let used = if chars[0] == '#' { 7 } else { 13 };
+    u32::from_str_radix(&hex, 16).ok().map(|rgb| (rgb, used))
+}
+
+/// Applies a single-character code, returning false if it is not one.
+fn apply_code(style: &mut Style, code: char) -> bool {
+    let code = code.to_ascii_lowercase();
+    if let Some(color) = NamedColor::from_code(code) {
+        *style = Style { color: Some(color.into()), ..Style::default() };
+        return true;
+    }
+    match code {
+        'k' => style.obfuscated = Some(true),
+        'l' => style.bold = Some(true),
+        'm' => style.strikethrough = Some(true),
+        'n' => style.underlined = Some(true),
+        'o' => style.italic = Some(true),
+        'r' => *style = Style::default(),
+        _ => return false,
+    }
+    true
+}
+
+/// The named color closest to an RGB color, for formats without RGB.
+fn nearest_named(rgb: u32) -> NamedColor {
+    let channels = |rgb: u32| [(rgb >> 16) as i32 & 0xFF, (rgb >> 8) as i32 & 0xFF, rgb as i32 & 0xFF];
+    let target = channels(rgb);
+    NamedColor::ALL
+        .into_iter()
+        .min_by_key(|color| {
+            channels(color.rgb()).iter().zip(target).map(|(a, b)| (a - b) * (a - b)).sum::<i32>()
+        })
+        .unwrap()
+}
+
+/// Writes the codes switching from the `current` style to `target`.
+fn push_style_change(out: &mut String, prefix: char, current: &Style, target: &Style) {
+    let flags = |style: &Style| {
+        [
+            (style.obfuscated, 'k'),
+            (style.bold, 'l'),
+            (style.strikethrough, 'm'),
+            (style.underlined, 'n'),
+            (style.italic, 'o'),
+        ]
+        .map(|(set, code)| (set == Some(true), code))
+    };
+    let (current_flags, target_flags) = (flags(current), flags(target));
+    let color = |style: &Style| style.color.map(|color| match color {
+        TextColor::Named(color) => color,
+        TextColor::Rgb(rgb) => nearest_named(rgb),
+    });
+
+    // Codes can only be turned off by a color or reset, which clears all of them
+    let removed = current_flags.iter().zip(&target_flags).any(|((was, _), (is, _))| *was && !is);
+    let reset = removed || color(current) != color(target);
+    if reset {
+        out.push(prefix);
+        out.push(color(target).map_or('r', NamedColor::code));
+    }
+    for ((was, _), (is, code)) in current_flags.iter().zip(target_flags) {
+        if is && (reset || !was) {
+            out.push(prefix);
+            out.push(code);
+        }
+    }
+}
+
+impl TextComponent {
+    /// Parses text with formatting codes starting with `prefix`, usually
+    /// [`SECTION_SIGN`] or [`AMPERSAND`]. A prefix not followed by a valid code
+    /// is kept as text.
+    pub fn from_legacy(text: &str, prefix: char) -> TextComponent {
+        let chars: Vec<char> = text.chars().collect();
+        let mut root = TextComponent::text("");
+        let mut style = Style::default();
+        let mut current = String::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i] == prefix && i + 1 < chars.len() {
+                let mut next = style.clone();
+                let used = if let Some((rgb, used)) = parse_rgb(&chars[i + 1..], prefix) {
+                    next = Style { color: Some(TextColor::Rgb(rgb)), ..Style::default() };
+                    Some(used)
+                } else if apply_code(&mut next, chars[i + 1]) {
+                    Some(1)
+                } else {
+                    None
+                };
+
+                if let Some(used) = used {
+                    if !current.is_empty() {
+                        let text = TextComponent { style: style.clone(), ..TextComponent::text(std::mem::take(&mut current)) };
+                        root.children.push(text);
+                    }
+                    style = next;
+                    i += 1 + used;
+                    continue;
+                }
+            }
+            current.push(chars[i]);
+            i += 1;
+        }
+        if !current.is_empty() {
+            root.children.push(TextComponent { style, ..TextComponent::text(current) });
+        }
+
+        match root.children.len() {
+            1 => root.children.pop().unwrap(),
+            _ => root,
+        }
+    }
+
+    /// Writes the component with formatting codes starting with `prefix`. Only
+    /// colors and decorations are kept, with RGB colors reduced to the nearest
+    /// named color so every client understands them. Text parsed from
+    /// `§#RRGGBB` therefore does not round-trip unless the color is a named one.
+    pub fn to_legacy(&self, prefix: char) -> String {
+        let mut out = String::new();
+        self.push_legacy(&mut out, prefix, &Style::default(), &mut Style::default());
+        out
+    }
+
+    fn push_legacy(&self, out: &mut String, prefix: char, parent: &Style, current: &mut Style) {
+        let style = self.style.inherit(parent);
+        let text = match &self.content {
+            Content::Text(text) => text.as_str(),
+            Content::Translatable { key, fallback, .. } => fallback.as_ref().unwrap_or(key),
+            Content::Keybind(key) => key,
+            Content::Score { .. } | Content::Selector { .. } | Content::Nbt { .. } => "",
+        };
+        if !text.is_empty() {
+            push_style_change(out, prefix, current, &style);
+            out.push_str(text);
+            *current = style.clone();
+        }
+        for child in &self.children {
+            child.push_legacy(out, prefix, &style, current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text_component::{NamedColor, TextColor, TextComponent, AMPERSAND, SECTION_SIGN};
+
+    #[test]
+    fn codes_round_trip() {
+        let component = TextComponent::from_legacy("&cHi &lthere", AMPERSAND);
+        assert_eq!(component.to_plain_text(), "Hi there");
+        assert_eq!(component.to_legacy(AMPERSAND), "&cHi &lthere");
+        assert_eq!(TextComponent::from_legacy(&component.to_legacy(AMPERSAND), AMPERSAND), component);
+    }
+
+    #[test]
+    fn invalid_codes_stay_text() {
+        assert_eq!(TextComponent::from_legacy("100% &zfun&", AMPERSAND).to_plain_text(), "100% &zfun&");
+    }
+
+    #[test]
+    fn rgb_colors() {
+        let component = TextComponent::from_legacy("§x§F§F§0§0§0§0Hi", SECTION_SIGN);
+        assert_eq!(component, TextComponent::from_legacy("§#FF0000Hi", SECTION_SIGN));
+        assert_eq!(component.to_json(), TextComponent::text("Hi").color(TextColor::Rgb(0xFF0000)).to_json());
+    }
+
+    #[test]
+    fn rgb_colors_become_named() {
+        let component = TextComponent::from_legacy("§#123456Hi", SECTION_SIGN);
+        assert_eq!(component.to_legacy(SECTION_SIGN), "§8Hi");
+        assert_ne!(TextComponent::from_legacy(&component.to_legacy(SECTION_SIGN), SECTION_SIGN), component);
+
+        let named = TextComponent::from_legacy("§#FF5555Hi", SECTION_SIGN);
+        assert_eq!(named.to_legacy(SECTION_SIGN), "§cHi");
+        assert_eq!(
+            TextComponent::from_legacy("§cHi", SECTION_SIGN).style.color,
+            Some(TextColor::Named(NamedColor::Red))
+        );
+    }
+}
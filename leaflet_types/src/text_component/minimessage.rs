@@ -0,0 +1,543 @@
+//! MiniMessage markup, like `<red>Hello <bold>world</bold>!`.
+//!
+//! Supported are colors (`<red>`, `<#FF5555>`, `<color:red>`), decorations and
+//! their negations (`<bold>`, `<!italic>`), `<reset>`, `<newline>`, `<click>`,
+//! `<hover>`, `<insert>`, `<font>` and `<shadow>`, and the `<lang>`, `<key>`,
+//! `<selector>`, `<score>` and `<nbt>` components. Arguments are separated by
+//! `:` and can be quoted with `'` or `"`. Unknown tags stay text, like in
+//! MiniMessage itself, and `\<` escapes a tag.
+
+use uuid::Uuid;
+use crate::identifier::Identifier;
+use crate::text_component::{ClickEvent, Content, HoverEvent, NbtSource, Style, TextColor, TextComponent};
+
+/// The alpha MiniMessage gives shadows without one.
+const DEFAULT_SHADOW_ALPHA: f32 = 0.25;
+
+/// A tag as written, with its quotes removed.
+struct Tag {
+    closing: bool,
+    negated: bool,
+    name: String,
+    args: Vec<String>,
+}
+
+impl Tag {
+    /// The arguments from `from` on joined again, for values that may contain `:`.
+    fn rest(&self, from: usize) -> Option<String> {
+        (self.args.len() > from).then(|| self.args[from..].join(":"))
+    }
+}
+
+/// Reads the tag at the start of `chars`, which begins with `<`. Returns the
+/// tag and the number of chars it spans.
+fn read_tag(chars: &[char]) -> Option<(Tag, usize)> {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+    let mut i = 1;
+    loop {
+        let c = *chars.get(i)?;
+        i += 1;
+        match (quote, c) {
+            (Some(_), '\\') => {
+                parts.last_mut()?.push(*chars.get(i)?);
+                i += 1;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => parts.last_mut()?.push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ':') => parts.push(String::new()),
+            (None, '>') => break,
+            (None, '<') => return None,
+            (None, c) => parts.last_mut()?.push(c),
+        }
+    }
+
+    let mut name = parts.remove(0);
+    let closing = name.starts_with('/');
+    if closing {
+        name.remove(0);
+    }
+    let negated = name.starts_with('!');
+    if negated {
+        name.remove(0);
+    }
+    if name.is_empty() {
+        return None;
+    }
+    Some((Tag { closing, negated, name: name.to_lowercase(), args: parts }, i))
+}
+
+/// The name a tag is closed by, so `</b>` closes `<bold>` and `</color>` any color.
+fn canonical_name(name: &str) -> &str {
+    match name {
+        "b" => "bold",
+        "i" | "em" => "italic",
+        "u" => "underlined",
+        "st" => "strikethrough",
+        "obf" => "obfuscated",
+        "c" | "colour" => "color",
+        "insertion" => "insert",
+        name if name.starts_with('#') || TextColor::parse(name).is_some() => "color",
+        name => name,
+    }
+}
+
+/// Parses `#RRGGBBAA`, or a color and an optional alpha from 0 to 1.
+fn parse_shadow(tag: &Tag) -> Option<u32> {
+    let color = tag.args.first()?;
+    if let Some(hex) = color.strip_prefix('#')
+        && hex.len() == 8
+    {
+        return u32::from_str_radix(hex, 16).ok().map(|rgba| rgba.rotate_right(8));
+    }
+    let rgb = TextColor::parse(color)?.rgb();
+    let alpha = match tag.args.get(1) {
+        Some(alpha) => alpha.parse::<f32>().ok()?,
+        None => DEFAULT_SHADOW_ALPHA,
+    };
+    Some(((alpha.clamp(0.0, 1.0) * 255.0).round() as u32) << 24 | rgb)
+}
+
+fn parse_click(tag: &Tag) -> Option<ClickEvent> {
+    let value = tag.rest(1)?;
+    Some(match tag.args.first()?.as_str() {
+        "open_url" => ClickEvent::OpenUrl(value),
+        "open_file" => ClickEvent::OpenFile(value),
+        "run_command" => ClickEvent::RunCommand(value),
+        "suggest_command" => ClickEvent::SuggestCommand(value),
+        "change_page" => ClickEvent::ChangePage(value.parse().ok()?),
+        "copy_to_clipboard" => ClickEvent::CopyToClipboard(value),
+        _ => return None,
+    })
+}
+
+fn parse_hover(tag: &Tag) -> Option<HoverEvent> {
+    let args = &tag.args[1.min(tag.args.len())..];
+    Some(match tag.args.first()?.as_str() {
+        "show_text" => HoverEvent::ShowText(Box::new(TextComponent::from_minimessage(&tag.rest(1)?))),
+        // The id may itself contain a `:`, so the count is only taken from the end
+        "show_item" => {
+            let (id, count) = match args.split_last()? {
+                (count, id) if !id.is_empty() && count.parse::<i32>().is_ok() => (id.join(":"), count.parse().ok()?),
+                _ => (args.join(":"), 1),
+            };
+            HoverEvent::ShowItem { id: Identifier::parse(&id), count, components: None }
+        }
+        "show_entity" => {
+            let position = args.iter().position(|arg| Uuid::parse_str(arg).is_ok())?;
+            HoverEvent::ShowEntity {
+                entity_type: Identifier::parse(&args[..position].join(":")),
+                uuid: Uuid::parse_str(&args[position]).ok()?,
+                name: (position + 1 < args.len())
+                    .then(|| Box::new(TextComponent::from_minimessage(&args[position + 1..].join(":")))),
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// The style a tag opens, if it is a style tag.
+fn parse_style(tag: &Tag) -> Option<Style> {
+    let mut style = Style::default();
+    let enabled = !tag.negated && tag.args.first().is_none_or(|arg| arg != "false");
+    match canonical_name(&tag.name) {
+        "bold" => style.bold = Some(enabled),
+        "italic" => style.italic = Some(enabled),
+        "underlined" => style.underlined = Some(enabled),
+        "strikethrough" => style.strikethrough = Some(enabled),
+        "obfuscated" => style.obfuscated = Some(enabled),
+        _ if tag.negated => return None,
+        "color" => {
+            let color = match tag.args.first() {
+                Some(color) if matches!(tag.name.as_str(), "color" | "colour" | "c") => color,
+                _ => &tag.name,
+            };
+            style.color = Some(TextColor::parse(color)?);
+        }
+        "click" => style.click_event = Some(parse_click(tag)?),
+        "hover" => style.hover_event = Some(parse_hover(tag)?),
+        "insert" => style.insertion = Some(tag.rest(0)?),
+        "font" => style.font = Some(Identifier::parse(&tag.rest(0)?)),
+        "shadow" => style.shadow_color = Some(parse_shadow(tag)?),
+        _ => return None,
+    }
+    Some(style)
+}
+
+/// The component a self-closing tag inserts.
+fn parse_component(tag: &Tag) -> Option<TextComponent> {
+    if tag.negated {
+        return None;
+    }
+    let args = &tag.args;
+    Some(match tag.name.as_str() {
+        "key" => TextComponent::keybind(tag.rest(0)?),
+        "lang" | "tr" | "translate" => TextComponent::translatable(
+            args.first()?.clone(),
+            args[1..].iter().map(|arg| TextComponent::from_minimessage(arg)).collect(),
+        ),
+        "lang_or" | "tr_or" | "translate_or" => TextComponent::new(Content::Translatable {
+            key: args.first()?.clone(),
+            fallback: Some(args.get(1)?.clone()),
+            args: args[2..].iter().map(|arg| TextComponent::from_minimessage(arg)).collect(),
+        }),
+        "selector" | "sel" => TextComponent::new(Content::Selector {
+            selector: args.first()?.clone(),
+            separator: args.get(1).map(|separator| Box::new(TextComponent::from_minimessage(separator))),
+        }),
+        "score" => TextComponent::score(args.first()?.clone(), args.get(1)?.clone()),
+        "nbt" | "data" => {
+            let source = match args.first()?.as_str() {
+                "block" => NbtSource::Block(args.get(1)?.clone()),
+                "entity" => NbtSource::Entity(args.get(1)?.clone()),
+                "storage" => NbtSource::Storage(Identifier::parse(args.get(1)?)),
+                _ => return None,
+            };
+            // Followed by an optional separator and `interpret`
+            let mut extra = args.get(3..).unwrap_or_default();
+            let interpret = extra.last().is_some_and(|arg| arg == "interpret");
+            if interpret {
+                extra = &extra[..extra.len() - 1];
+            }
+            let separator = extra.first();
+            TextComponent::new(Content::Nbt {
+                path: args.get(2)?.clone(),
+                interpret,
+                separator: separator.map(|separator| Box::new(TextComponent::from_minimessage(separator))),
+                source,
+            })
+        }
+        _ => return None,
+    })
+}
+
+/// An open tag and the component collecting what follows it.
+struct Frame {
+    name: String,
+    component: TextComponent,
+}
+
+struct Parser {
+    stack: Vec<Frame>,
+    text: String,
+}
+
+impl Parser {
+    fn top(&mut self) -> &mut TextComponent {
+        &mut self.stack.last_mut().unwrap().component
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            let text = TextComponent::text(std::mem::take(&mut self.text));
+            self.top().children.push(text);
+        }
+    }
+
+    /// Closes the topmost frame, moving leading plain text into its content.
+    fn pop(&mut self) {
+        let mut component = self.stack.pop().unwrap().component;
+        if let Content::Text(content) = &component.content
+            && content.is_empty()
+            && let Some(TextComponent { content: Content::Text(_), style, children }) = component.children.first()
+            && style.is_empty()
+            && children.is_empty()
+        {
+            component.content = component.children.remove(0).content;
+        }
+        // Tags opened together, like `<red><bold>`, become a single component
+        if let Content::Text(content) = &component.content
+            && content.is_empty()
+            && let [child] = component.children.as_slice()
+            && child.style.inherit(&component.style) == component.style.inherit(&child.style)
+        {
+            let mut child = component.children.pop().unwrap();
+            child.style = child.style.inherit(&component.style);
+            component = child;
+        }
+        self.top().children.push(component);
+    }
+
+    /// Handles a tag, returning false if it is not a known tag.
+    fn tag(&mut self, tag: Tag) -> bool {
+        if tag.closing {
+            let name = canonical_name(&tag.name);
+            let Some(index) = self.stack.iter().rposition(|frame| frame.name == name) else {
+                return false;
+            };
+            self.flush_text();
+            while self.stack.len() > index {
+                self.pop();
+            }
+            return true;
+        }
+
+        if matches!(tag.name.as_str(), "newline" | "br") && !tag.negated {
+            self.text.push('\n');
+        } else if tag.name == "reset" && !tag.negated {
+            self.flush_text();
+            while self.stack.len() > 1 {
+                self.pop();
+            }
+        } else if let Some(style) = parse_style(&tag) {
+            self.flush_text();
+            let component = TextComponent { style, ..TextComponent::text("") };
+            self.stack.push(Frame { name: canonical_name(&tag.name).to_string(), component });
+        } else if let Some(component) = parse_component(&tag) {
+            self.flush_text();
+            self.top().children.push(component);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+/// Quotes an argument if it could be mistaken for markup.
+fn push_arg(out: &mut String, arg: &str) {
+    out.push(':');
+    if !arg.is_empty() && !arg.contains([':', '<', '>', '\'', '"', '\\']) {
+        out.push_str(arg);
+        return;
+    }
+    out.push('\'');
+    for c in arg.chars() {
+        if matches!(c, '\'' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('\'');
+}
+
+fn push_tag(out: &mut String, name: &str, args: &[&str]) {
+    out.push('<');
+    out.push_str(name);
+    for arg in args {
+        push_arg(out, arg);
+    }
+    out.push('>');
+}
+
+/// The tags for a style, with the names closing them. Events that have no tag
+/// are left out.
+fn style_tags(style: &Style) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    let mut tag = |name: &str, args: &[&str], close: &str| {
+        let mut open = String::new();
+        push_tag(&mut open, name, args);
+        tags.push((open, close.to_string()));
+    };
+
+    if let Some(color) = style.color {
+        let name = color.to_string();
+        tag(&name, &[], &name);
+    }
+    let flags = [
+        ("bold", style.bold),
+        ("italic", style.italic),
+        ("underlined", style.underlined),
+        ("strikethrough", style.strikethrough),
+        ("obfuscated", style.obfuscated),
+    ];
+    for (name, value) in flags {
+        match value {
+            Some(true) => tag(name, &[], name),
+            Some(false) => tag(&format!("!{name}"), &[], name),
+            None => {}
+        }
+    }
+    if let Some(argb) = style.shadow_color {
+        tag("shadow", &[&format!("#{:08X}", argb.rotate_left(8))], "shadow");
+    }
+    if let Some(font) = &style.font {
+        tag("font", &[&font.to_string()], "font");
+    }
+    if let Some(insertion) = &style.insertion {
+        tag("insert", &[insertion], "insert");
+    }
+    let click = match &style.click_event {
+        Some(ClickEvent::ChangePage(page)) => Some(("change_page", page.to_string())),
+        Some(event @ (ClickEvent::OpenUrl(value)
+            | ClickEvent::OpenFile(value)
+            | ClickEvent::RunCommand(value)
+            | ClickEvent::SuggestCommand(value)
+            | ClickEvent::CopyToClipboard(value))) => Some((event.action(), value.clone())),
+        _ => None,
+    };
+    if let Some((action, value)) = click {
+        tag("click", &[action, &value], "click");
+    }
+    match &style.hover_event {
+        Some(HoverEvent::ShowText(text)) => tag("hover", &["show_text", &text.to_minimessage()], "hover"),
+        Some(HoverEvent::ShowItem { id, count, .. }) => {
+            tag("hover", &["show_item", &id.to_string(), &count.to_string()], "hover")
+        }
+        Some(HoverEvent::ShowEntity { entity_type, uuid, name }) => {
+            let (entity_type, uuid) = (entity_type.to_string(), uuid.to_string());
+            match name {
+                Some(name) => tag("hover", &["show_entity", &entity_type, &uuid, &name.to_minimessage()], "hover"),
+                None => tag("hover", &["show_entity", &entity_type, &uuid], "hover"),
+            }
+        }
+        None => {}
+    }
+    tags
+}
+
+fn push_content(out: &mut String, content: &Content) {
+    match content {
+        Content::Text(text) => {
+            for c in text.chars() {
+                if matches!(c, '<' | '\\') {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+        }
+        Content::Translatable { key, fallback, args } => {
+            let args: Vec<String> = args.iter().map(TextComponent::to_minimessage).collect();
+            let mut all: Vec<&str> = vec![key];
+            let name = match fallback {
+                Some(fallback) => {
+                    all.push(fallback);
+                    "lang_or"
+                }
+                None => "lang",
+            };
+            all.extend(args.iter().map(String::as_str));
+            push_tag(out, name, &all);
+        }
+        Content::Score { name, objective } => push_tag(out, "score", &[name, objective]),
+        Content::Selector { selector, separator } => match separator {
+            Some(separator) => push_tag(out, "selector", &[selector, &separator.to_minimessage()]),
+            None => push_tag(out, "selector", &[selector]),
+        },
+        Content::Keybind(key) => push_tag(out, "key", &[key]),
+        Content::Nbt { path, interpret, separator, source } => {
+            let (kind, id) = match source {
+                NbtSource::Block(position) => ("block", position.clone()),
+                NbtSource::Entity(selector) => ("entity", selector.clone()),
+                NbtSource::Storage(storage) => ("storage", storage.to_string()),
+            };
+            let separator = separator.as_ref().map(|separator| separator.to_minimessage());
+            let mut args = vec![kind, &id, path];
+            args.extend(separator.as_deref());
+            if *interpret {
+                args.push("interpret");
+            }
+            push_tag(out, "nbt", &args);
+        }
+    }
+}
+
+impl TextComponent {
+    /// Parses MiniMessage markup, see the [module docs](self). This never fails,
+    /// anything that is not a valid tag is kept as text.
+    pub fn from_minimessage(input: &str) -> TextComponent {
+        let chars: Vec<char> = input.chars().collect();
+        let root = Frame { name: String::new(), component: TextComponent::text("") };
+        let mut parser = Parser { stack: vec![root], text: String::new() };
+
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' if matches!(chars.get(i + 1), Some('<' | '\\')) => {
+                    parser.text.push(chars[i + 1]);
+                    i += 2;
+                    continue;
+                }
+                '<' => {
+                    if let Some((tag, length)) = read_tag(&chars[i..])
+                        && parser.tag(tag)
+                    {
+                        i += length;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            parser.text.push(chars[i]);
+            i += 1;
+        }
+
+        parser.flush_text();
+        while parser.stack.len() > 1 {
+            parser.pop();
+        }
+        let mut root = parser.stack.pop().unwrap().component;
+        match root.children.len() {
+            0 => root,
+            1 => root.children.pop().unwrap(),
+            _ => root,
+        }
+    }
+
+    /// Writes the component as MiniMessage markup. Click events that have no tag,
+    /// like dialogs, and the data components of items are left out.
+    pub fn to_minimessage(&self) -> String {
+        let mut out = String::new();
+        self.push_minimessage(&mut out);
+        out
+    }
+
+    fn push_minimessage(&self, out: &mut String) {
+        let tags = style_tags(&self.style);
+        for (open, _) in &tags {
+            out.push_str(open);
+        }
+        push_content(out, &self.content);
+        for child in &self.children {
+            child.push_minimessage(out);
+        }
+        for (_, close) in tags.iter().rev() {
+            out.push_str("</");
+            out.push_str(close);
+            out.push('>');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text_component::{HoverEvent, NamedColor, TextColor, TextComponent};
+
+    /// Parses `input`, writes it and parses the result again, which must give the same component.
+    fn round_trip(input: &str) -> TextComponent {
+        let component = TextComponent::from_minimessage(input);
+        assert_eq!(TextComponent::from_minimessage(&component.to_minimessage()), component);
+        component
+    }
+
+    #[test]
+    fn nested_styles() {
+        let component = round_trip("<red>Hello <bold>world");
+        assert_eq!(component.to_plain_text(), "Hello world");
+        assert_eq!(component.style.color, Some(TextColor::Named(NamedColor::Red)));
+        assert_eq!(component.children[0].style.bold, Some(true));
+        assert_eq!(component.to_minimessage(), "<red>Hello <bold>world</bold></red>");
+    }
+
+    #[test]
+    fn hover_text() {
+        let component = round_trip("<hover:show_text:'<red>a'>");
+        let Some(HoverEvent::ShowText(text)) = &component.style.hover_event else {
+            panic!("expected a show_text hover event");
+        };
+        assert_eq!(**text, TextComponent::text("a").color(NamedColor::Red));
+    }
+
+    #[test]
+    fn escapes_and_unknown_tags() {
+        assert_eq!(round_trip("\\<red>not a tag").to_plain_text(), "<red>not a tag");
+        assert_eq!(round_trip("<unknown>text").to_plain_text(), "<unknown>text");
+    }
+
+    #[test]
+    fn newlines_and_rgb() {
+        let component = round_trip("<#123456>a<newline>b");
+        assert_eq!(component.to_plain_text(), "a\nb");
+        assert_eq!(component.style.color, Some(TextColor::Rgb(0x123456)));
+    }
+}
@@ -0,0 +1,269 @@
+//! Text components, the formatted text used for chat, disconnect reasons and
+//! the server list.
+//!
+//! Components are sent as JSON in the status and login states and as network
+//! NBT everywhere else. Both encodings share the same structure, following
+//! the format of 1.21.5 and later.
+
+mod color;
+mod event;
+mod json;
+mod legacy;
+mod minimessage;
+mod nbt;
+
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::identifier::Identifier;
+
+pub use color::{NamedColor, TextColor};
+pub use event::{ClickEvent, HoverEvent};
+pub use legacy::{AMPERSAND, SECTION_SIGN};
+
+/// A component with its style and children. Children inherit the style of
+/// their parent, unless they override it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextComponent {
+    pub content: Content,
+    pub style: Style,
+    /// Appended after the content, called `extra` on the wire.
+    pub children: Vec<TextComponent>,
+}
+
+/// What a component displays.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Text(String),
+    /// A translation key, with the components filling in its `%s` placeholders.
+    Translatable {
+        key: String,
+        /// Shown if the client does not know the key.
+        fallback: Option<String>,
+        args: Vec<TextComponent>,
+    },
+    /// The score of an entity on an objective.
+    Score { name: String, objective: String },
+    /// The names of the entities matching a selector.
+    Selector {
+        selector: String,
+        separator: Option<Box<TextComponent>>,
+    },
+    /// The key bound to an action, like `key.jump`.
+    Keybind(String),
+    /// Values read from block, entity or storage NBT.
+    Nbt {
+        path: String,
+        /// Whether the values are themselves components to show.
+        interpret: bool,
+        separator: Option<Box<TextComponent>>,
+        source: NbtSource,
+    },
+}
+
+impl Default for Content {
+    fn default() -> Self {
+        Content::Text(String::new())
+    }
+}
+
+/// Where an NBT component reads its values from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NbtSource {
+    /// The block entity at the given coordinates.
+    Block(String),
+    /// The entities matching a selector.
+    Entity(String),
+    /// A command storage.
+    Storage(Identifier),
+}
+
+/// Formatting of a component. Unset fields are inherited from the parent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<TextColor>,
+    /// The shadow color as ARGB.
+    pub shadow_color: Option<u32>,
+    pub font: Option<Identifier>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underlined: Option<bool>,
+    pub strikethrough: Option<bool>,
+    pub obfuscated: Option<bool>,
+    /// Inserted into the chat input when shift-clicking the component.
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    pub fn is_empty(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// This style with unset fields taken from `parent`.
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            shadow_color: self.shadow_color.or(parent.shadow_color),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+}
+
+/// A text component that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextComponentError(pub String);
+
+impl Display for TextComponentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid text component: {}", self.0)
+    }
+}
+
+impl std::error::Error for TextComponentError {}
+
+pub type TextComponentResult<T> = Result<T, TextComponentError>;
+
+impl TextComponent {
+    pub fn new(content: Content) -> Self {
+        Self { content, style: Style::default(), children: Vec::new() }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(Content::Text(text.into()))
+    }
+
+    pub fn translatable(key: impl Into<String>, args: Vec<TextComponent>) -> Self {
+        Self::new(Content::Translatable { key: key.into(), fallback: None, args })
+    }
+
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> Self {
+        Self::new(Content::Score { name: name.into(), objective: objective.into() })
+    }
+
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self::new(Content::Selector { selector: selector.into(), separator: None })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> Self {
+        Self::new(Content::Keybind(key.into()))
+    }
+
+    pub fn nbt(path: impl Into<String>, source: NbtSource) -> Self {
+        Self::new(Content::Nbt { path: path.into(), interpret: false, separator: None, source })
+    }
+
+    pub fn color(mut self, color: impl Into<TextColor>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+
+    pub fn shadow_color(mut self, argb: u32) -> Self {
+        self.style.shadow_color = Some(argb);
+        self
+    }
+
+    pub fn font(mut self, font: Identifier) -> Self {
+        self.style.font = Some(font);
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.style.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.style.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.style.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn click_event(mut self, event: ClickEvent) -> Self {
+        self.style.click_event = Some(event);
+        self
+    }
+
+    pub fn hover_event(mut self, event: HoverEvent) -> Self {
+        self.style.hover_event = Some(event);
+        self
+    }
+
+    pub fn append(mut self, child: impl Into<TextComponent>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+
+    /// The text without formatting. Translatable components show their fallback
+    /// or key, since the server has no translations, and components resolved by
+    /// the client show nothing.
+    pub fn to_plain_text(&self) -> String {
+        let mut out = String::new();
+        self.push_plain_text(&mut out);
+        out
+    }
+
+    fn push_plain_text(&self, out: &mut String) {
+        match &self.content {
+            Content::Text(text) => out.push_str(text),
+            Content::Translatable { key, fallback, .. } => out.push_str(fallback.as_ref().unwrap_or(key)),
+            Content::Keybind(key) => out.push_str(key),
+            Content::Score { .. } | Content::Selector { .. } | Content::Nbt { .. } => {}
+        }
+        for child in &self.children {
+            child.push_plain_text(out);
+        }
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        TextComponent::text(text)
+    }
+}
+
+impl Serialize for TextComponent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TextComponent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        TextComponent::from_json(&value).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,32 @@
+use leaflet_nbt::{NBTTag, TagContent};
+use leaflet_network_buffer::{BufferError, BufferResult, McBuf, NetworkType};
+use crate::text_component::{TextComponent, TextComponentResult};
+
+impl TextComponent {
+    /// Encodes the component as NBT. This has the same structure as the JSON
+    /// encoding and is converted from it with [`TagContent::from_json`], so
+    /// booleans become bytes and lists of strings and compounds are wrapped.
+    pub fn to_nbt(&self) -> TagContent {
+        TagContent::from_json(&self.to_json())
+    }
+
+    pub fn from_nbt(tag: &TagContent) -> TextComponentResult<TextComponent> {
+        TextComponent::from_json(&tag.to_json())
+    }
+}
+
+/// Network NBT, as used in packets outside the status and login states.
+impl NetworkType for TextComponent {
+    fn read(buf: &mut McBuf) -> BufferResult<Self> {
+        let tag = NBTTag::read(buf)?;
+        TextComponent::from_nbt(&tag.content).map_err(|_| BufferError::Generic("invalid text component"))
+    }
+
+    fn write(&self, buf: &mut McBuf) {
+        buf.write_network_type(&NBTTag { content: self.to_nbt(), name: None });
+    }
+
+    fn try_write(&self, buf: &mut McBuf) -> BufferResult<()> {
+        buf.try_write_network_type(&NBTTag { content: self.to_nbt(), name: None })
+    }
+}
@@ -10,7 +10,7 @@ impl<const L: usize> PaletteContent<L> {
         }
         let entries_per_long = 64 / bits_per_entry;
         let total_entries = L * L * L;
-        let long_count = (total_entries + entries_per_long - 1) / entries_per_long;
+        let long_count = total_entries.div_ceil(entries_per_long);
         Self { bits_per_entry, data: vec![0; long_count] }
     }
 
@@ -1,3 +1,4 @@
 pub mod container;
 mod content;
+#[allow(clippy::module_inception)]
 mod palette;
\ No newline at end of file
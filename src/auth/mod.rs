@@ -0,0 +1,103 @@
+pub mod session;
+
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use md5::Md5;
+use uuid::{Builder, Uuid};
+
+/// Longest name a player can have.
+pub const MAX_USERNAME_LENGTH: usize = 16;
+
+/// The RSA key pair used to exchange the shared secret during login.
+pub struct ServerKeys {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ServerKeys {
+    /// Generates a 1024 bit key pair, the size vanilla clients expect.
+    pub fn generate() -> Self {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).expect("Failed to generate RSA key pair");
+        let public_key_der = RsaPublicKey::from(&private_key)
+            .to_public_key_der()
+            .expect("Failed to encode RSA public key")
+            .into_vec();
+        Self { private_key, public_key_der }
+    }
+
+    /// The public key as an X.509 `SubjectPublicKeyInfo`, as sent in the Encryption Request.
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    /// Decrypts data the client encrypted with the public key.
+    pub fn decrypt(&self, data: &[u8]) -> Option<Vec<u8>> {
+        self.private_key.decrypt(Pkcs1v15Encrypt, data).ok()
+    }
+}
+
+/// The server id sent to the session server: a SHA-1 digest printed as a
+/// signed hexadecimal number, like Java's `BigInteger.toString(16)`.
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+    let mut digest: [u8; 20] = Sha1::new()
+        .chain_update(server_id)
+        .chain_update(shared_secret)
+        .chain_update(public_key_der)
+        .finalize()
+        .into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // Two's complement to get the magnitude
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (value, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = value;
+            carry = overflow;
+        }
+    }
+
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    let hex = hex.trim_start_matches('0');
+    match (negative, hex.is_empty()) {
+        (_, true) => "0".into(),
+        (true, false) => format!("-{hex}"),
+        (false, false) => hex.into(),
+    }
+}
+
+/// The UUID vanilla gives players in offline mode, a version 3 UUID of
+/// `"OfflinePlayer:" + name` like Java's `UUID.nameUUIDFromBytes`.
+pub fn offline_uuid(username: &str) -> Uuid {
+    let digest: [u8; 16] = Md5::new()
+        .chain_update("OfflinePlayer:")
+        .chain_update(username)
+        .finalize()
+        .into();
+    Builder::from_md5_bytes(digest).into_uuid()
+}
+
+/// Whether `username` could belong to an account: 1 to 16 letters, digits or underscores.
+pub fn is_valid_username(username: &str) -> bool {
+    (1..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+    use super::{offline_uuid, server_hash};
+
+    #[test]
+    fn server_hash_matches_vanilla() {
+        assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    #[test]
+    fn offline_uuid_matches_vanilla() {
+        assert_eq!(offline_uuid("Notch"), Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap());
+    }
+}
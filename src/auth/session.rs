@@ -0,0 +1,79 @@
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::time::Duration;
+use serde::Deserialize;
+use uuid::Uuid;
+use leaflet_types::game_profile::{GameProfile, GameProfileProperty};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum SessionError {
+    /// The request failed or the session server answered with an error status.
+    Request(String),
+    /// The response was not a valid profile.
+    InvalidResponse(String),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Request(message) => write!(f, "session server request failed: {message}"),
+            SessionError::InvalidResponse(message) => write!(f, "invalid session server response: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[derive(Deserialize)]
+struct SessionProfile {
+    id: String,
+    name: String,
+    #[serde(default)]
+    properties: Vec<SessionProperty>,
+}
+
+#[derive(Deserialize)]
+struct SessionProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+/// Asks the session server whether `username` joined with `server_hash`.
+/// Returns the player's profile, including the signed `textures` property,
+/// or `None` if the player did not authenticate.
+///
+/// This blocks until the session server answers, so call it off the connection's task.
+pub fn has_joined(url: &str, username: &str, server_hash: &str, ip: Option<IpAddr>) -> Result<Option<GameProfile>, SessionError> {
+    let mut request = ureq::get(url)
+        .timeout(TIMEOUT)
+        .query("username", username)
+        .query("serverId", server_hash);
+    if let Some(ip) = ip {
+        request = request.query("ip", &ip.to_string());
+    }
+
+    let response = request.call().map_err(|e| SessionError::Request(e.to_string()))?;
+    if response.status() == 204 {
+        return Ok(None);
+    }
+
+    let body = response.into_string().map_err(|e| SessionError::Request(e.to_string()))?;
+    let profile: SessionProfile = serde_json::from_str(&body).map_err(|e| SessionError::InvalidResponse(e.to_string()))?;
+    let uuid = Uuid::parse_str(&profile.id).map_err(|e| SessionError::InvalidResponse(e.to_string()))?;
+    Ok(Some(GameProfile {
+        uuid,
+        username: profile.name,
+        properties: profile
+            .properties
+            .into_iter()
+            .map(|property| GameProfileProperty {
+                name: property.name,
+                value: property.value,
+                signature: property.signature,
+            })
+            .collect(),
+    }))
+}
@@ -1,105 +1,364 @@
+use std::net::SocketAddr;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use crate::packet_reader::PacketReader;
+use crate::packet_writer::PacketWriter;
 use leaflet_network_buffer::McBuf;
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 use leaflet_protocol::{handle_configuration_serverbound, handle_handshake_serverbound, handle_login_serverbound, handle_play_serverbound, handle_status_serverbound, ConnectionState, Packet};
 use leaflet_protocol::clientbound::configuration::configuration_keep_alive::ClientboundConfigurationKeepAlivePacket;
+use leaflet_protocol::clientbound::configuration::disconnect::ClientboundConfigurationDisconnectPacket;
+use leaflet_protocol::clientbound::play::disconnect::ClientboundDisconnectPacket;
 use leaflet_protocol::clientbound::play::keep_alive::ClientboundKeepAlivePacket;
+use leaflet_protocol::encryption::PacketCipher;
+use leaflet_protocol::version::ProtocolVersion;
+use leaflet_protocol::login::{ClientboundLoginDisconnectPacket, ClientboundLoginSuccessPacket, ClientboundSetCompressionPacket};
 use leaflet_types::game_profile::GameProfile;
+use leaflet_types::text_component::TextComponent;
+use uuid::Uuid;
+use crate::auth::session::{self, SessionError};
+use crate::forwarding::LegacyForwarding;
 use crate::handlers::{PacketHandler};
+use crate::legacy_ping::{Detection, LegacyPing};
+use crate::server::Server;
 
 const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for the rest of a legacy ping that arrived split up.
+const LEGACY_PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+type SessionResult = Result<Option<GameProfile>, SessionError>;
+
+/// A login waiting for the client's Encryption Response.
+pub struct PendingLogin {
+    pub username: String,
+    pub verify_token: [u8; 4],
+}
 
 pub struct ClientConnection {
     connection: TcpStream,
+    pub server: Arc<Server>,
+    pub remote_address: SocketAddr,
     pub state: ConnectionState,
+    /// The protocol number from the handshake.
+    pub protocol_version: i32,
+    /// The version spoken with the client: theirs if it is supported, the latest otherwise.
+    pub version: ProtocolVersion,
     packet_reader: PacketReader,
+    packet_writer: PacketWriter,
+    cipher: Option<PacketCipher>,
+    /// The first bytes, held back until it is clear whether they are a legacy
+    /// ping. `None` once that is decided, as only they can be one.
+    initial_data: Option<Vec<u8>>,
+    /// When the first bytes arrived.
+    first_data_at: SystemTime,
     packet_queue: Vec<McBuf>,
+    closed: bool,
     pub last_keep_alive: SystemTime,
     pub gameprofile: Option<GameProfile>,
+    pub pending_login: Option<PendingLogin>,
+    /// Player information from BungeeCord, read from the handshake.
+    pub legacy_forwarding: Option<LegacyForwarding>,
+    /// The id of the Login Plugin Request asking Velocity for the player's information.
+    pub velocity_message_id: Option<i32>,
+    session_lookup: Option<Receiver<SessionResult>>,
+    /// Set once the player is registered with the server, to remove them again on drop.
+    registered_player: Option<Uuid>,
 }
 
 impl ClientConnection {
-    pub fn new(stream: TcpStream) -> Self {
+    pub fn new(stream: TcpStream, remote_address: SocketAddr, server: Arc<Server>) -> Self {
         Self {
             connection: stream,
+            server,
+            remote_address,
             state: ConnectionState::Handshake,
+            protocol_version: 0,
+            version: ProtocolVersion::LATEST,
             packet_reader: PacketReader::new(),
+            packet_writer: PacketWriter::new(),
+            cipher: None,
+            initial_data: Some(Vec::new()),
+            first_data_at: SystemTime::now(),
             packet_queue: Vec::new(),
+            closed: false,
             last_keep_alive: SystemTime::now(),
             gameprofile: None,
+            pending_login: None,
+            legacy_forwarding: None,
+            velocity_message_id: None,
+            session_lookup: None,
+            registered_player: None,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Stops handling the connection. Packets queued so far are still sent
+    /// before the socket is shut down at the end of the current poll.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Tells the client why it is being disconnected, with the disconnect packet
+    /// of the current state, then closes the connection. The handshake and
+    /// status states have no such packet, so there the connection is just closed.
+    pub fn disconnect(&mut self, reason: impl Into<TextComponent>) {
+        let reason = reason.into();
+        println!("Disconnecting {}: {}", self.remote_address, reason.to_plain_text());
+        match self.state {
+            ConnectionState::Handshake | ConnectionState::Status => {}
+            ConnectionState::Login => self.queue_packet(&ClientboundLoginDisconnectPacket {
+                reason: reason.to_json().to_string(),
+            }),
+            ConnectionState::Configuration => self.queue_packet(&ClientboundConfigurationDisconnectPacket { reason }),
+            ConnectionState::Play => self.queue_packet(&ClientboundDisconnectPacket { reason }),
         }
+        self.close();
     }
 
     pub async fn poll(&mut self) {
+        if self.closed {
+            return;
+        }
+
         let mut buf = [0u8; 1024];
         let read = self.connection.try_read(&mut buf);
-        if let Ok(read) = read {
-            self.packet_reader.append(&buf[..read]);
-            self.process_incoming_packets();
-        }
-
-        if self.last_keep_alive.elapsed().unwrap_or_default() > KEEP_ALIVE_INTERVAL {
-            self.last_keep_alive = SystemTime::now();
-            match self.state {
-                ConnectionState::Configuration => self.queue_packet(&ClientboundConfigurationKeepAlivePacket {
-                    id: 0
-                }),
-                ConnectionState::Play => self.queue_packet(&ClientboundKeepAlivePacket {
-                    id: 0
-                }),
-                _ => {}
+        match read {
+            Ok(0) => {
+                self.closed = true;
+                return;
             }
+            Ok(read) => match &mut self.initial_data {
+                Some(initial_data) => {
+                    if initial_data.is_empty() {
+                        self.first_data_at = SystemTime::now();
+                    }
+                    initial_data.extend_from_slice(&buf[..read]);
+                }
+                None => self.receive(&mut buf[..read]),
+            },
+            Err(_) => {}
+        }
+        self.check_legacy_ping();
+
+        if !self.closed {
+            self.poll_session_lookup();
+            self.send_keep_alive();
         }
         self.process_outgoing_packets().await;
+        if self.closed {
+            self.connection.shutdown().await.ok();
+        }
     }
 
+    fn send_keep_alive(&mut self) {
+        if self.last_keep_alive.elapsed().unwrap_or_default() <= KEEP_ALIVE_INTERVAL {
+            return;
+        }
+        self.last_keep_alive = SystemTime::now();
+        match self.state {
+            ConnectionState::Configuration => self.queue_packet(&ClientboundConfigurationKeepAlivePacket {
+                id: 0
+            }),
+            ConnectionState::Play => self.queue_packet(&ClientboundKeepAlivePacket {
+                id: 0
+            }),
+            _ => {}
+        }
+    }
+
+    /// Queues `packet` with the id and layout of the negotiated version. Packets
+    /// the version does not have are dropped. A packet that cannot be encoded
+    /// disconnects the client, as vanilla does.
     pub fn queue_packet<T: Packet>(&mut self, packet: &T) {
+        let Some(id) = T::id(self.version) else {
+            println!("Not sending {packet:?}, which {} clients do not have", self.version.name());
+            return;
+        };
         let mut buf = McBuf::new();
-        buf.write_var_int(T::ID);
-        buf.write_network_type(packet);
-        self.packet_queue.push(buf);
+        buf.write_var_int(id);
+        if let Err(e) = packet.write_versioned(&mut buf, self.version) {
+            println!("Failed to encode {packet:?} for {}: {e:?}", self.remote_address);
+            // Closing first keeps a disconnect packet that fails to encode from trying again
+            if !self.closed {
+                self.close();
+                self.disconnect(TextComponent::translatable(
+                    "disconnect.genericReason",
+                    vec![format!("Internal Exception: {e:?}").into()],
+                ));
+            }
+            return;
+        }
+        let mut frame = self.packet_writer.write_packet(&buf);
+        if let Some(cipher) = &mut self.cipher {
+            cipher.encrypt(frame.as_mut_slice());
+        }
+        self.packet_queue.push(frame);
+    }
+
+    /// Encrypts everything sent and received from now on. Packets queued before
+    /// this call are still sent in plain text.
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        let mut cipher = PacketCipher::new(shared_secret);
+        // Anything that arrived after the packet enabling encryption is already encrypted
+        cipher.decrypt(self.packet_reader.pending_mut());
+        self.cipher = Some(cipher);
+    }
+
+    /// Finishes the login with `profile`, enabling compression first if configured.
+    pub fn complete_login(&mut self, profile: GameProfile) {
+        if !self.server.add_player(&profile) {
+            self.disconnect("A player with this name is already online");
+            return;
+        }
+        self.registered_player = Some(profile.uuid);
+
+        if let Some(threshold) = self.server.config.compression_threshold {
+            self.enable_compression(threshold);
+        }
+
+        self.gameprofile = Some(profile.clone());
+        self.queue_packet(&ClientboundLoginSuccessPacket { gameprofile: profile });
+    }
+
+    /// Asks the session server whether `username` authenticated for `server_hash`
+    /// on a separate thread. The login completes once it answers.
+    pub fn start_session_lookup(&mut self, username: String, server_hash: String) {
+        let url = self.server.config.session_server_url.clone();
+        let ip = self.server.config.prevent_proxy_connections.then(|| self.remote_address.ip());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            sender.send(session::has_joined(&url, &username, &server_hash, ip)).ok();
+        });
+        self.session_lookup = Some(receiver);
+    }
+
+    /// Answers a ping from a client older than 1.7 and closes the connection.
+    fn receive(&mut self, bytes: &mut [u8]) {
+        if let Some(cipher) = &mut self.cipher {
+            cipher.decrypt(bytes);
+        }
+        self.packet_reader.append(bytes);
+        self.process_incoming_packets();
+    }
+
+    /// Decides whether the first bytes are a legacy ping, then answers it or
+    /// handles them as packets. A ping split across reads is waited for.
+    fn check_legacy_ping(&mut self) {
+        let Some(initial_data) = &self.initial_data else {
+            return;
+        };
+        if initial_data.is_empty() {
+            return;
+        }
+
+        let timed_out = self.first_data_at.elapsed().unwrap_or_default() > LEGACY_PING_TIMEOUT;
+        let ping = match LegacyPing::detect(initial_data) {
+            Detection::Incomplete(_) if !timed_out => return,
+            Detection::Ping(ping) | Detection::Incomplete(ping) => Some(ping),
+            Detection::NotPing => None,
+        };
+
+        let mut data = self.initial_data.take().unwrap_or_default();
+        match ping {
+            Some(ping) => self.answer_legacy_ping(ping),
+            None => self.receive(&mut data),
+        }
+    }
+
+    fn answer_legacy_ping(&mut self, ping: LegacyPing) {
+        println!("Legacy ping ({ping:?}) from {}", self.remote_address);
+        let status = self.server.status(ProtocolVersion::LATEST);
+        self.packet_queue.push(McBuf::from_bytes(&ping.response(&status)));
+        self.close();
+    }
+
+    fn poll_session_lookup(&mut self) {
+        let Some(receiver) = &self.session_lookup else {
+            return;
+        };
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(SessionError::Request("lookup thread stopped".into())),
+        };
+        self.session_lookup = None;
+
+        match result {
+            Ok(Some(profile)) => {
+                println!("Authenticated {} ({})", profile.username, profile.uuid);
+                self.complete_login(profile);
+            }
+            Ok(None) => self.disconnect("Failed to verify username!"),
+            Err(e) => {
+                println!("Could not verify session: {e}");
+                self.disconnect("Authentication servers are down. Please try again later, sorry!");
+            }
+        }
+    }
+
+    /// Sends Set Compression and compresses packets of at least `threshold` bytes
+    /// in both directions from then on.
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.queue_packet(&ClientboundSetCompressionPacket {
+            threshold: threshold as i32,
+        });
+        self.packet_reader.set_compression(Some(threshold));
+        self.packet_writer.set_compression(Some(threshold));
     }
 
     fn process_incoming_packets(&mut self) {
-        while let Some(mut packet) = self.packet_reader.read_packet() {
-            match self.state {
-                ConnectionState::Handshake => {
-                    handle_handshake_serverbound(self, &mut packet, &PacketHandler).unwrap()
-                }
-                ConnectionState::Status => {
-                    handle_status_serverbound(self, &mut packet, &PacketHandler).unwrap()
-                }
-                ConnectionState::Login => {
-                    handle_login_serverbound(self, &mut packet, &PacketHandler).unwrap()
-                }
-                ConnectionState::Configuration => {
-                    handle_configuration_serverbound(self, &mut packet, &PacketHandler).unwrap()
-                }
-                ConnectionState::Play => {
-                    handle_play_serverbound(self, &mut packet, &PacketHandler).unwrap()
-                }
+        while !self.closed {
+            let version = self.version;
+            let result = match self.packet_reader.read_packet() {
+                Ok(Some(mut packet)) => match self.state {
+                    ConnectionState::Handshake => handle_handshake_serverbound(self, &mut packet, version, &PacketHandler),
+                    ConnectionState::Status => handle_status_serverbound(self, &mut packet, version, &PacketHandler),
+                    ConnectionState::Login => handle_login_serverbound(self, &mut packet, version, &PacketHandler),
+                    ConnectionState::Configuration => {
+                        handle_configuration_serverbound(self, &mut packet, version, &PacketHandler)
+                    }
+                    ConnectionState::Play => handle_play_serverbound(self, &mut packet, version, &PacketHandler),
+                },
+                Ok(None) => break,
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = result {
+                println!("Failed to handle a packet from {}: {e:?}", self.remote_address);
+                self.disconnect(TextComponent::translatable(
+                    "disconnect.genericReason",
+                    vec![format!("Internal Exception: {e:?}").into()],
+                ));
             }
         }
     }
 
+    /// Sends the queued packets. If the client is gone, the connection is closed.
     async fn process_outgoing_packets(&mut self) {
         for packet in self.packet_queue.drain(..) {
-            let len = packet.length();
-
-            let mut rem = len;
-            while (rem & !0x7F) != 0 {
-                self.connection
-                    .write_u8((rem & 0x7F) as u8 | 0x80)
-                    .await
-                    .unwrap();
-                rem >>= 7;
+            if let Err(e) = self.connection.write_all(packet.as_slice()).await {
+                println!("Failed to send to {}: {e}", self.remote_address);
+                self.closed = true;
+                return;
             }
-            self.connection.write_u8(rem as u8).await.unwrap();
+        }
+        if let Err(e) = self.connection.flush().await {
+            println!("Failed to send to {}: {e}", self.remote_address);
+            self.closed = true;
+        }
+    }
+}
 
-            self.connection.write(packet.as_slice()).await.unwrap();
+impl Drop for ClientConnection {
+    fn drop(&mut self) {
+        if let Some(uuid) = self.registered_player {
+            self.server.remove_player(&uuid);
         }
-        self.connection.flush().await.unwrap();
     }
 }
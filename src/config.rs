@@ -0,0 +1,84 @@
+use std::path::Path;
+use serde::Deserialize;
+
+/// How player information reaches the server when running behind a proxy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingMode {
+    /// Players connect directly.
+    #[default]
+    None,
+    /// BungeeCord's legacy forwarding through the handshake. Anyone who can
+    /// reach the server can claim any identity, so firewall it off.
+    Legacy,
+    /// Velocity's modern forwarding, signed with `forwarding_secret`.
+    Modern,
+}
+
+/// Server settings, read from a JSON file. Missing fields take their default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Whether players are authenticated with the session server.
+    pub online_mode: bool,
+    /// In offline mode, replace the UUID sent by the client with the one vanilla
+    /// derives from the name. Turn off only behind a proxy that sends real UUIDs.
+    pub enforce_offline_uuids: bool,
+    /// The session server's `hasJoined` endpoint.
+    pub session_server_url: String,
+    /// Whether the session server should check that players join from the
+    /// address they authenticated from.
+    pub prevent_proxy_connections: bool,
+    /// Trust player information from a proxy instead of authenticating players
+    /// directly. `online_mode` is ignored, since the proxy handles it.
+    pub forwarding: ForwardingMode,
+    /// The secret shared with Velocity for modern forwarding. Required when `forwarding` is modern.
+    pub forwarding_secret: String,
+    /// The message shown in the server list, as MiniMessage markup like `<red>Hello`.
+    pub motd: String,
+    pub max_players: usize,
+    /// A 64x64 PNG shown next to the server in the server list.
+    pub favicon: String,
+    /// Tells clients that chat messages must be signed.
+    pub enforce_secure_chat: bool,
+    /// Packets of at least this many bytes are compressed, `None` disables compression.
+    pub compression_threshold: Option<usize>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            online_mode: true,
+            enforce_offline_uuids: true,
+            session_server_url: "https://sessionserver.mojang.com/session/minecraft/hasJoined".into(),
+            prevent_proxy_connections: false,
+            forwarding: ForwardingMode::None,
+            forwarding_secret: String::new(),
+            motd: "Hello, world!".into(),
+            max_players: 20,
+            favicon: "server-icon.png".into(),
+            enforce_secure_chat: false,
+            compression_threshold: Some(256),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads the config from `path`, or uses the defaults if the file does not exist.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let config: ServerConfig = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e),
+        };
+
+        // Anyone can sign with an empty secret and claim any identity
+        if config.forwarding == ForwardingMode::Modern && config.forwarding_secret.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "modern forwarding requires a forwarding_secret",
+            ));
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,230 @@
+//! Player information forwarded by a proxy in front of the server.
+
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+use leaflet_network_buffer::{BufferError, McBuf};
+use leaflet_types::game_profile::{GameProfile, GameProfileProperty};
+use leaflet_types::identifier::Identifier;
+
+/// The newest forwarding version understood, sent in the request. Versions 2
+/// and 3 add chat signing keys after the profile, which are ignored.
+pub const VELOCITY_MAX_VERSION: u8 = 4;
+
+const SIGNATURE_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub enum ForwardingError {
+    /// The data was not signed with the configured secret.
+    InvalidSignature,
+    UnsupportedVersion(i32),
+    Malformed(String),
+}
+
+impl Display for ForwardingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForwardingError::InvalidSignature => write!(f, "forwarding data has an invalid signature"),
+            ForwardingError::UnsupportedVersion(version) => write!(f, "unsupported forwarding version {version}"),
+            ForwardingError::Malformed(message) => write!(f, "malformed forwarding data: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ForwardingError {}
+
+impl From<BufferError> for ForwardingError {
+    fn from(value: BufferError) -> Self {
+        ForwardingError::Malformed(format!("{value:?}"))
+    }
+}
+
+/// The login plugin channel Velocity answers with the player's information.
+pub fn velocity_channel() -> Identifier {
+    Identifier::new("velocity", "player_info")
+}
+
+/// Data sent by Velocity's modern forwarding.
+#[derive(Debug, Clone)]
+pub struct VelocityForwarding {
+    pub address: IpAddr,
+    pub profile: GameProfile,
+}
+
+impl VelocityForwarding {
+    /// Checks the HMAC-SHA256 signature at the start of `data` against `secret`,
+    /// then reads the forwarded player.
+    pub fn read(secret: &[u8], data: &[u8]) -> Result<Self, ForwardingError> {
+        if data.len() < SIGNATURE_LENGTH {
+            return Err(ForwardingError::Malformed("missing signature".into()));
+        }
+        let (signature, payload) = data.split_at(SIGNATURE_LENGTH);
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac.verify_slice(signature).map_err(|_| ForwardingError::InvalidSignature)?;
+
+        let mut buf = McBuf::from_bytes(payload);
+        let version = buf.read_var_int()?;
+        if !(1..=VELOCITY_MAX_VERSION as i32).contains(&version) {
+            return Err(ForwardingError::UnsupportedVersion(version));
+        }
+
+        let address = buf.read_string(255)?;
+        let address = address
+            .parse()
+            .map_err(|_| ForwardingError::Malformed(format!("invalid address `{address}`")))?;
+        let profile = buf.read_network_type::<GameProfile>()?;
+        Ok(Self { address, profile })
+    }
+}
+
+#[derive(Deserialize)]
+struct LegacyProperty {
+    name: String,
+    value: String,
+    signature: Option<String>,
+}
+
+/// Data BungeeCord's legacy forwarding packs into the handshake's server
+/// address, as `host\0ip\0uuid` with an optional `\0properties` JSON array.
+#[derive(Debug, Clone)]
+pub struct LegacyForwarding {
+    /// The address the player connected to.
+    pub host: String,
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    pub properties: Vec<GameProfileProperty>,
+}
+
+impl LegacyForwarding {
+    pub fn parse(server_address: &str) -> Result<Self, ForwardingError> {
+        let parts: Vec<&str> = server_address.split('\0').collect();
+        if !(3..=4).contains(&parts.len()) {
+            return Err(ForwardingError::Malformed("expected 3 or 4 fields".into()));
+        }
+
+        let address = parts[1]
+            .parse()
+            .map_err(|_| ForwardingError::Malformed(format!("invalid address `{}`", parts[1])))?;
+        let uuid = Uuid::parse_str(parts[2])
+            .map_err(|_| ForwardingError::Malformed(format!("invalid UUID `{}`", parts[2])))?;
+        let properties = match parts.get(3) {
+            Some(json) => serde_json::from_str::<Vec<LegacyProperty>>(json)
+                .map_err(|e| ForwardingError::Malformed(e.to_string()))?
+                .into_iter()
+                .map(|property| GameProfileProperty {
+                    name: property.name,
+                    value: property.value,
+                    signature: property.signature,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            host: parts[0].to_string(),
+            address,
+            uuid,
+            properties,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+    use uuid::Uuid;
+    use leaflet_network_buffer::McBuf;
+    use leaflet_types::game_profile::GameProfile;
+    use super::{ForwardingError, LegacyForwarding, VelocityForwarding};
+
+    const SECRET: &[u8] = b"secret";
+    const UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+    fn payload(version: i32) -> Vec<u8> {
+        let mut buf = McBuf::new();
+        buf.write_var_int(version);
+        buf.write_string("127.0.0.2");
+        buf.write_network_type(&GameProfile {
+            uuid: Uuid::parse_str(UUID).unwrap(),
+            username: "Notch".into(),
+            properties: Vec::new(),
+        });
+        buf.as_slice().to_vec()
+    }
+
+    fn sign(secret: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn velocity_reads_signed_data() {
+        let forwarding = VelocityForwarding::read(SECRET, &sign(SECRET, &payload(1))).unwrap();
+        assert_eq!(forwarding.address.to_string(), "127.0.0.2");
+        assert_eq!(forwarding.profile.username, "Notch");
+    }
+
+    #[test]
+    fn velocity_rejects_bad_signature() {
+        let result = VelocityForwarding::read(SECRET, &sign(b"other", &payload(1)));
+        assert!(matches!(result, Err(ForwardingError::InvalidSignature)));
+
+        let mut data = sign(SECRET, &payload(1));
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        assert!(matches!(VelocityForwarding::read(SECRET, &data), Err(ForwardingError::InvalidSignature)));
+    }
+
+    #[test]
+    fn velocity_rejects_unsupported_version() {
+        for version in [0, 5] {
+            let result = VelocityForwarding::read(SECRET, &sign(SECRET, &payload(version)));
+            assert!(matches!(result, Err(ForwardingError::UnsupportedVersion(v)) if v == version));
+        }
+    }
+
+    #[test]
+    fn velocity_rejects_truncated_data() {
+        let payload = payload(1);
+        let truncated = &payload[..payload.len() - 4];
+        let result = VelocityForwarding::read(SECRET, &sign(SECRET, truncated));
+        assert!(matches!(result, Err(ForwardingError::Malformed(_))));
+        assert!(matches!(VelocityForwarding::read(SECRET, &[0; 16]), Err(ForwardingError::Malformed(_))));
+    }
+
+    #[test]
+    fn bungee_with_three_fields() {
+        let forwarding = LegacyForwarding::parse(&format!("example.com\x00127.0.0.2\x00{}", UUID.replace('-', ""))).unwrap();
+        assert_eq!(forwarding.host, "example.com");
+        assert_eq!(forwarding.address.to_string(), "127.0.0.2");
+        assert_eq!(forwarding.uuid.to_string(), UUID);
+        assert!(forwarding.properties.is_empty());
+    }
+
+    #[test]
+    fn bungee_with_properties() {
+        let properties = r#"[{"name":"textures","value":"abc","signature":"sig"}]"#;
+        let forwarding = LegacyForwarding::parse(&format!("example.com\x00::1\x00{UUID}\x00{properties}")).unwrap();
+        assert_eq!(forwarding.address.to_string(), "::1");
+        assert_eq!(forwarding.properties.len(), 1);
+        assert_eq!(forwarding.properties[0].name, "textures");
+        assert_eq!(forwarding.properties[0].signature.as_deref(), Some("sig"));
+    }
+
+    #[test]
+    fn bungee_rejects_malformed_handshakes() {
+        assert!(LegacyForwarding::parse("example.com").is_err());
+        assert!(LegacyForwarding::parse(&format!("example.com\x00not an ip\x00{UUID}")).is_err());
+        assert!(LegacyForwarding::parse("example.com\x00127.0.0.2\x00not a uuid").is_err());
+        assert!(LegacyForwarding::parse(&format!("example.com\x00127.0.0.2\x00{UUID}\x00not json")).is_err());
+        assert!(LegacyForwarding::parse(&format!("a\x00127.0.0.2\x00{UUID}\x00[]\x00extra")).is_err());
+    }
+}
@@ -4,13 +4,14 @@ use leaflet_protocol::clientbound::configuration::finish_configuration::Clientbo
 use leaflet_protocol::clientbound::configuration::known_packs::{ClientboundKnownPacksPacket, KnownPack};
 use leaflet_protocol::clientbound::configuration::registry_data::{ClientboundRegistryDataPacket, RegistryEntry};
 use leaflet_protocol::clientbound::configuration::update_tags::{ClientboundUpdateTagsPacket, RegistryTags, TagEntry};
-use leaflet_protocol::{ConfigurationServerboundHandler, ConnectionState};
+use leaflet_protocol::{ConfigurationServerboundHandler, ConnectionState, Packet};
 use leaflet_protocol::clientbound::play::login::ClientboundPlayLoginPacket;
 use leaflet_protocol::serverbound::configuration::accept_code_of_conduct::ServerboundAcceptCodeOfConductPacket;
 use leaflet_protocol::serverbound::configuration::acknowledge_finish_configuration::ServerboundAcknowledgeFinishConfigurationPacket;
 use leaflet_protocol::serverbound::configuration::client_information::ServerboundClientInformationPacket;
 use leaflet_protocol::serverbound::configuration::configuration_keep_alive_response::ServerboundConfigurationKeepAliveResponsePacket;
 use leaflet_protocol::serverbound::configuration::known_packs::ServerboundKnownPacksPacket;
+use leaflet_protocol::version::ProtocolVersion;
 use leaflet_types::identifier::Identifier;
 use crate::client_connection::ClientConnection;
 use crate::handlers::PacketHandler;
@@ -25,12 +26,19 @@ impl ConfigurationServerboundHandler for PacketHandler {
     ) {
         println!("Client information: {:?}", packet);
 
+        // Releases sharing a protocol version still have their own core pack, so
+        // offer each of them and let the client pick its own
         let response_packet = ClientboundKnownPacksPacket {
-            known_packs: vec![KnownPack {
-                namespace: "minecraft".to_string(),
-                path: "core".to_string(),
-                version: "1.21.11".to_string(),
-            }],
+            known_packs: connection
+                .version
+                .releases()
+                .iter()
+                .map(|release| KnownPack {
+                    namespace: "minecraft".to_string(),
+                    path: "core".to_string(),
+                    version: release.to_string(),
+                })
+                .collect(),
         };
         connection.queue_packet(&response_packet);
     }
@@ -115,26 +123,35 @@ impl ConfigurationServerboundHandler for PacketHandler {
             registry_id: Identifier::minecraft("wolf_variant"),
             entries: vec![RegistryEntry::empty(Identifier::minecraft("ashen"))],
         });
-        connection.queue_packet(&ClientboundRegistryDataPacket {
-            registry_id: Identifier::minecraft("zombie_nautilus_variant"),
-            entries: vec![RegistryEntry::empty(Identifier::minecraft("warm"))],
-        });
-        connection.queue_packet(&ClientboundRegistryDataPacket {
-            registry_id: Identifier::minecraft("timeline"),
-            entries: vec![
-                RegistryEntry::empty(Identifier::minecraft("villager_schedule")),
-                RegistryEntry::empty(Identifier::minecraft("early_game")),
-                RegistryEntry::empty(Identifier::minecraft("day")),
-                RegistryEntry::empty(Identifier::minecraft("moon")),
-            ],
-        });
-
-        connection.queue_packet(&ClientboundUpdateTagsPacket {
-            tagged_registries: vec![RegistryTags {
+        // Registries added in 1.21.11
+        if connection.version >= ProtocolVersion::V1_21_11 {
+            connection.queue_packet(&ClientboundRegistryDataPacket {
+                registry_id: Identifier::minecraft("zombie_nautilus_variant"),
+                entries: vec![RegistryEntry::empty(Identifier::minecraft("warm"))],
+            });
+            connection.queue_packet(&ClientboundRegistryDataPacket {
                 registry_id: Identifier::minecraft("timeline"),
-                entries: vec![TagEntry::empty(Identifier::minecraft("in_overworld"))],
-            }],
-        });
+                entries: vec![
+                    RegistryEntry::empty(Identifier::minecraft("villager_schedule")),
+                    RegistryEntry::empty(Identifier::minecraft("early_game")),
+                    RegistryEntry::empty(Identifier::minecraft("day")),
+                    RegistryEntry::empty(Identifier::minecraft("moon")),
+                ],
+            });
+
+            connection.queue_packet(&ClientboundUpdateTagsPacket {
+                tagged_registries: vec![RegistryTags {
+                    registry_id: Identifier::minecraft("timeline"),
+                    entries: vec![TagEntry::empty(Identifier::minecraft("in_overworld"))],
+                }],
+            });
+        }
+
+        // Older clients have no code of conduct to accept
+        if ClientboundCodeOfConductPacket::id(connection.version).is_none() {
+            connection.queue_packet(&ClientboundFinishConfigurationPacket);
+            return;
+        }
 
         connection.queue_packet(&ClientboundCodeOfConductPacket {
             message: "By joining this server, you agree to the terms outlined in the Terms and Conditions and Privacy Policy at https://ultradev.app/terms. You further agree to the harvesting of your organs at an unspecified later date.".to_string()
@@ -169,7 +186,7 @@ impl ConfigurationServerboundHandler for PacketHandler {
             entity_id: 0,
             is_hardcore: false,
             dimension_names: vec![Identifier::minecraft("overworld")],
-            max_players: 20,
+            max_players: connection.server.config.max_players as i32,
             view_distance: 8,
             simulation_distance: 8,
             reduced_debug_info: false,
@@ -1,17 +1,57 @@
 use leaflet_network_buffer::McBuf;
 use leaflet_protocol::{ConnectionState, HandshakeServerboundHandler};
 use leaflet_protocol::serverbound::handshake::serverbound_handshake::ServerboundHandshakePacket;
+use leaflet_protocol::version::ProtocolVersion;
+use leaflet_types::text_component::TextComponent;
 use crate::client_connection::ClientConnection;
+use crate::config::ForwardingMode;
+use crate::forwarding::LegacyForwarding;
 use crate::handlers::PacketHandler;
 
 impl HandshakeServerboundHandler for PacketHandler {
     type ClientType = ClientConnection;
 
     fn on_handshake(&self, connection: &mut Self::ClientType, packet: ServerboundHandshakePacket) {
+        let version = ProtocolVersion::from_protocol(packet.protocol_version);
+        connection.protocol_version = packet.protocol_version;
+        if let Some(version) = version {
+            connection.version = version;
+        }
+
         match packet.intent {
             1 => connection.state = ConnectionState::Status,
             2 | 3 => connection.state = ConnectionState::Login,
-            _ => panic!("Invalid intent: {}", packet.intent),
+            intent => {
+                connection.disconnect(format!("Invalid intent {intent}"));
+                return;
+            }
+        }
+
+        if let ConnectionState::Login = connection.state
+            && version.is_none()
+        {
+            let key = if packet.protocol_version < ProtocolVersion::OLDEST.protocol() {
+                "multiplayer.disconnect.outdated_client"
+            } else {
+                "multiplayer.disconnect.outdated_server"
+            };
+            connection.disconnect(TextComponent::translatable(key, vec![ProtocolVersion::supported_range().into()]));
+            return;
+        }
+
+        if let ConnectionState::Login = connection.state
+            && connection.server.config.forwarding == ForwardingMode::Legacy
+        {
+            match LegacyForwarding::parse(&packet.server_address) {
+                Ok(forwarding) => {
+                    connection.remote_address.set_ip(forwarding.address);
+                    connection.legacy_forwarding = Some(forwarding);
+                }
+                Err(e) => {
+                    println!("Invalid BungeeCord forwarding data: {e}");
+                    connection.disconnect("If you wish to use IP forwarding, please enable it in your BungeeCord config as well!");
+                }
+            }
         }
     }
 
@@ -1,8 +1,11 @@
 use leaflet_network_buffer::McBuf;
-use leaflet_protocol::login::{ClientboundLoginSuccessPacket, ServerboundLoginAcknowledgedPacket, ServerboundLoginStartPacket};
+use leaflet_protocol::login::{ClientboundEncryptionRequestPacket, ClientboundLoginPluginRequestPacket, ServerboundEncryptionResponsePacket, ServerboundLoginAcknowledgedPacket, ServerboundLoginPluginResponsePacket, ServerboundLoginStartPacket};
 use leaflet_protocol::{ConnectionState, LoginServerboundHandler};
 use leaflet_types::game_profile::GameProfile;
-use crate::client_connection::ClientConnection;
+use crate::auth;
+use crate::config::ForwardingMode;
+use crate::forwarding::{self, VelocityForwarding};
+use crate::client_connection::{ClientConnection, PendingLogin};
 use crate::handlers::PacketHandler;
 
 impl LoginServerboundHandler for PacketHandler {
@@ -15,16 +18,122 @@ impl LoginServerboundHandler for PacketHandler {
     ) {
         println!("Login start: {} ({})", packet.username, packet.uuid);
 
-        let gameprofile = GameProfile {
-            uuid: packet.uuid,
+        if !auth::is_valid_username(&packet.username) {
+            connection.disconnect("Invalid username");
+            return;
+        }
+
+        let config = &connection.server.config;
+        match config.forwarding {
+            ForwardingMode::Legacy => {
+                let Some(forwarding) = connection.legacy_forwarding.take() else {
+                    connection.disconnect("Missing BungeeCord forwarding data");
+                    return;
+                };
+                connection.complete_login(GameProfile {
+                    uuid: forwarding.uuid,
+                    username: packet.username,
+                    properties: forwarding.properties,
+                });
+                return;
+            }
+            ForwardingMode::Modern => {
+                let message_id = rand::random::<i32>() & i32::MAX;
+                connection.velocity_message_id = Some(message_id);
+                connection.queue_packet(&ClientboundLoginPluginRequestPacket {
+                    message_id,
+                    channel: forwarding::velocity_channel(),
+                    data: vec![forwarding::VELOCITY_MAX_VERSION].into(),
+                });
+                return;
+            }
+            ForwardingMode::None => {}
+        }
+
+        if !config.online_mode {
+            let uuid = if config.enforce_offline_uuids {
+                auth::offline_uuid(&packet.username)
+            } else {
+                packet.uuid
+            };
+            connection.complete_login(GameProfile {
+                uuid,
+                username: packet.username,
+                properties: vec![],
+            });
+            return;
+        }
+
+        let verify_token: [u8; 4] = rand::random();
+        connection.pending_login = Some(PendingLogin {
             username: packet.username,
-            properties: vec![],
+            verify_token,
+        });
+
+        let request_packet = ClientboundEncryptionRequestPacket {
+            server_id: String::new(),
+            public_key: connection.server.keys.public_key_der().to_vec(),
+            verify_token: verify_token.to_vec(),
+            should_authenticate: true,
+        };
+        connection.queue_packet(&request_packet);
+    }
+
+    fn on_encryption_response(
+        &self,
+        connection: &mut Self::ClientType,
+        packet: ServerboundEncryptionResponsePacket,
+    ) {
+        let Some(pending) = connection.pending_login.take() else {
+            connection.disconnect("Unexpected Encryption Response");
+            return;
+        };
+
+        let keys = &connection.server.keys;
+        if keys.decrypt(&packet.verify_token).as_deref() != Some(&pending.verify_token[..]) {
+            connection.disconnect("Invalid verify token");
+            return;
+        }
+        let Some(shared_secret) = keys
+            .decrypt(&packet.shared_secret)
+            .and_then(|secret| <[u8; 16]>::try_from(secret).ok())
+        else {
+            connection.disconnect("Invalid shared secret");
+            return;
         };
 
-        connection.gameprofile = Some(gameprofile.clone());
+        let server_hash = auth::server_hash("", &shared_secret, keys.public_key_der());
+        connection.enable_encryption(&shared_secret);
+        connection.start_session_lookup(pending.username, server_hash);
+    }
+
+    fn on_login_plugin_response(
+        &self,
+        connection: &mut Self::ClientType,
+        packet: ServerboundLoginPluginResponsePacket,
+    ) {
+        if connection.velocity_message_id != Some(packet.message_id) {
+            println!("Received Login Plugin Response for unknown message {}", packet.message_id);
+            return;
+        }
+        connection.velocity_message_id = None;
+
+        if !packet.successful {
+            connection.disconnect("This server requires you to connect with Velocity.");
+            return;
+        }
 
-        let response_packet = ClientboundLoginSuccessPacket { gameprofile };
-        connection.queue_packet(&response_packet);
+        let secret = connection.server.config.forwarding_secret.as_bytes();
+        match VelocityForwarding::read(secret, &packet.data.0) {
+            Ok(forwarding) => {
+                connection.remote_address.set_ip(forwarding.address);
+                connection.complete_login(forwarding.profile);
+            }
+            Err(e) => {
+                println!("Invalid Velocity forwarding data: {e}");
+                connection.disconnect("Unable to verify player details");
+            }
+        }
     }
 
     fn on_login_acknowledged(
@@ -13,7 +13,7 @@ impl StatusServerboundHandler for PacketHandler {
         _packet: ServerboundStatusRequestPacket,
     ) {
         let response_packet = ClientboundStatusResponsePacket {
-            response_payload: "{\"version\":{\"name\":\"1.21.11\",\"protocol\":774},\"description\":{\"text\":\"Hello, world!\"}}".to_string()
+            response_payload: connection.server.status(connection.version).to_json(),
         };
 
         connection.queue_packet(&response_packet);
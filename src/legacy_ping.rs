@@ -0,0 +1,156 @@
+//! The server list ping of clients from before 1.7, which is not framed like
+//! later packets. Vanilla still answers it, and so do we.
+
+use leaflet_types::server_status::ServerStatus;
+use leaflet_types::text_component::SECTION_SIGN;
+
+/// The protocol version reported to legacy clients, which vanilla sets so
+/// they always show the server as incompatible.
+const LEGACY_PROTOCOL: i32 = 127;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3: just `0xFE`.
+    Beta,
+    /// 1.4 and 1.5: `0xFE 0x01`.
+    V1_4,
+    /// 1.6: `0xFE 0x01`, followed by an `MC|PingHost` plugin message.
+    V1_6,
+}
+
+/// What the first bytes received on a connection are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detection {
+    Ping(LegacyPing),
+    /// Regular framed packets.
+    NotPing,
+    /// The start of a legacy ping that was split up and may still grow. If
+    /// nothing more arrives, it is the given ping.
+    Incomplete(LegacyPing),
+}
+
+impl LegacyPing {
+    /// Checks whether the first bytes received on a connection are a legacy
+    /// ping, the same way vanilla does. A frame length can start with `0xFE 0x01`
+    /// too, but is then followed by more than a valid `MC|PingHost` message.
+    pub fn detect(bytes: &[u8]) -> Detection {
+        match bytes {
+            [0xFE] => Detection::Incomplete(LegacyPing::Beta),
+            [0xFE, 0x01] => Detection::Incomplete(LegacyPing::V1_4),
+            [0xFE, 0x01, rest @ ..] => match check_ping_host(rest) {
+                Some(true) => Detection::Ping(LegacyPing::V1_6),
+                Some(false) => Detection::NotPing,
+                None => Detection::Incomplete(LegacyPing::V1_4),
+            },
+            _ => Detection::NotPing,
+        }
+    }
+
+    /// The kick packet carrying the status, in the format this client understands.
+    pub fn response(self, status: &ServerStatus) -> Vec<u8> {
+        let (online, max) = status.players.as_ref().map_or((0, 0), |players| (players.online, players.max));
+        let message = match self {
+            // Beta clients split on the section sign, so the MOTD cannot have colors
+            LegacyPing::Beta => format!(
+                "{}\u{a7}{online}\u{a7}{max}",
+                status.description.to_plain_text().replace(SECTION_SIGN, ""),
+            ),
+            LegacyPing::V1_4 | LegacyPing::V1_6 => format!(
+                "\u{a7}1\0{LEGACY_PROTOCOL}\0{}\0{}\0{online}\0{max}",
+                status.version.name,
+                status.description.to_legacy(SECTION_SIGN),
+            ),
+        };
+
+        let units: Vec<u16> = message.encode_utf16().collect();
+        let mut packet = Vec::with_capacity(3 + units.len() * 2);
+        packet.push(0xFF);
+        packet.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for unit in units {
+            packet.extend_from_slice(&unit.to_be_bytes());
+        }
+        packet
+    }
+}
+
+/// Whether `bytes` is a valid `MC|PingHost` plugin message, or `None` if it
+/// is the start of one.
+fn check_ping_host(bytes: &[u8]) -> Option<bool> {
+    const CHANNEL: &str = "MC|PingHost";
+
+    let [0xFA, rest @ ..] = bytes else {
+        return Some(false);
+    };
+    let (name_length, rest) = read_u16(rest)?;
+    if name_length as usize != CHANNEL.len() {
+        return Some(false);
+    }
+    let name_bytes = name_length as usize * 2;
+    if rest.len() < name_bytes {
+        return None;
+    }
+    let (name, rest) = rest.split_at(name_bytes);
+    let name: Vec<u16> = name.chunks_exact(2).map(|unit| u16::from_be_bytes([unit[0], unit[1]])).collect();
+    if String::from_utf16_lossy(&name) != CHANNEL {
+        return Some(false);
+    }
+
+    // A protocol byte, the host string and an int port
+    let (length, data) = read_u16(rest)?;
+    if length < 7 || data.len() > length as usize {
+        return Some(false);
+    }
+    (data.len() == length as usize).then_some(true)
+}
+
+fn read_u16(bytes: &[u8]) -> Option<(u16, &[u8])> {
+    match bytes {
+        [high, low, rest @ ..] => Some((u16::from_be_bytes([*high, *low]), rest)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Detection, LegacyPing};
+
+    /// The ping a 1.6 client sends, connecting to `localhost:25565`.
+    fn ping_1_6() -> Vec<u8> {
+        let mut bytes = vec![0xFE, 0x01, 0xFA, 0x00, 0x0B];
+        bytes.extend("MC|PingHost".encode_utf16().flat_map(u16::to_be_bytes));
+        let host: Vec<u8> = "localhost".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        bytes.extend_from_slice(&(7 + host.len() as u16).to_be_bytes());
+        bytes.push(74);
+        bytes.extend_from_slice(&9u16.to_be_bytes());
+        bytes.extend_from_slice(&host);
+        bytes.extend_from_slice(&25565i32.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn detects_complete_pings() {
+        assert_eq!(LegacyPing::detect(&ping_1_6()), Detection::Ping(LegacyPing::V1_6));
+    }
+
+    #[test]
+    fn waits_for_split_pings() {
+        let ping = ping_1_6();
+        assert_eq!(LegacyPing::detect(&ping[..1]), Detection::Incomplete(LegacyPing::Beta));
+        assert_eq!(LegacyPing::detect(&ping[..2]), Detection::Incomplete(LegacyPing::V1_4));
+        for end in 3..ping.len() {
+            assert_eq!(LegacyPing::detect(&ping[..end]), Detection::Incomplete(LegacyPing::V1_4));
+        }
+    }
+
+    #[test]
+    fn rejects_framed_packets() {
+        // A handshake frame, and a 254 byte frame whose length looks like a 1.4 ping
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00, 0xFA, 0x05]), Detection::NotPing);
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01, 0x00, 0xFA, 0x05]), Detection::NotPing);
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01, 0xFA, 0x00, 0x03]), Detection::NotPing);
+
+        let mut too_long = ping_1_6();
+        too_long.push(0);
+        assert_eq!(LegacyPing::detect(&too_long), Detection::NotPing);
+    }
+}
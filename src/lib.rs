@@ -1,6 +1,12 @@
 extern crate core;
 
 pub mod packet_reader;
+pub mod packet_writer;
 pub mod client_connection;
-mod registry;
+pub mod registry;
 pub mod handlers;
+pub mod auth;
+pub mod config;
+pub mod forwarding;
+pub mod legacy_ping;
+pub mod server;
@@ -1,22 +1,30 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
 use leaflet_rs::client_connection::ClientConnection;
+use leaflet_rs::config::ServerConfig;
+use leaflet_rs::server::Server;
 use tokio::net::{TcpListener, TcpStream};
 
 #[tokio::main]
 async fn main() -> Result<(), tokio::io::Error> {
+    let server = Arc::new(Server::new(ServerConfig::load("leaflet.json")?));
     let listener = TcpListener::bind("127.0.0.1:25565").await?;
 
     loop {
-        let (socket, _) = listener.accept().await?;
+        let (socket, address) = listener.accept().await?;
+        let server = server.clone();
         tokio::spawn(async move {
-            process_socket(socket).await.ok();
+            process_socket(socket, address, server).await.ok();
         });
     }
 }
 
-async fn process_socket(socket: TcpStream) -> Result<(), tokio::io::Error> {
-    let mut connection = ClientConnection::new(socket);
+async fn process_socket(socket: TcpStream, address: SocketAddr, server: Arc<Server>) -> Result<(), tokio::io::Error> {
+    let mut connection = ClientConnection::new(socket, address, server);
 
-    loop {
+    while !connection.is_closed() {
         connection.poll().await;
     }
+
+    Ok(())
 }
@@ -1,48 +1,188 @@
-use leaflet_network_buffer::McBuf;
+use std::io::Read;
+use flate2::read::ZlibDecoder;
+use leaflet_network_buffer::{BufferError, BufferResult, McBuf};
+
+/// Longest a packet may claim to be once decompressed, the same limit vanilla uses.
+pub const MAX_DECOMPRESSED_LENGTH: usize = 8 * 1024 * 1024;
 
 pub struct PacketReader {
-    data: Vec<u8>
+    data: Vec<u8>,
+    compression_threshold: Option<usize>,
 }
 
 impl PacketReader {
     pub fn new() -> Self {
-        Self { data: Vec::new() }
+        Self { data: Vec::new(), compression_threshold: None }
     }
 
     pub fn append(&mut self, bytes: &[u8]) {
         self.data.extend_from_slice(bytes);
     }
+
+    /// Bytes received but not yet read as packets.
+    pub fn pending_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Switches to the compressed frame format, or back if `threshold` is `None`.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+}
+
+impl Default for PacketReader {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PacketReader {
-    fn try_read_varint(&mut self, start_index: usize) -> Option<(i32, usize)> {
-        let mut i = 0i32;
-        let mut index = 0;
-        loop {
-            if index > 5 || start_index + index >= self.data.len() {
-                return None;
+    /// Reads the frame length, which is at most 3 bytes long like in vanilla.
+    fn try_read_frame_length(&self) -> BufferResult<Option<(usize, usize)>> {
+        let mut length = 0usize;
+        for (index, byte) in self.data.iter().take(3).enumerate() {
+            length |= ((byte & 0x7F) as usize) << (index * 7);
+            if (byte & 0x80) == 0 {
+                return Ok(Some((length, index + 1)));
             }
+        }
 
-            i |= ((self.data[start_index + index] & 0x7F) as i32) << index * 7;
-            index += 1;
-
-            if (self.data[start_index + index] & 0x80) == 0 {
-                return Some((i, index));
-            }
+        if self.data.len() >= 3 {
+            Err(BufferError::VarIntOverflow)
+        } else {
+            Ok(None)
         }
     }
 
-    pub fn read_packet(&mut self) -> Option<McBuf> {
-        let (length, offset) = self.try_read_varint(0)?;
-        let length = length as usize;
+    /// Takes the next complete packet off the buffer, returning its id and body.
+    pub fn read_packet(&mut self) -> BufferResult<Option<McBuf>> {
+        let Some((length, offset)) = self.try_read_frame_length()? else {
+            return Ok(None);
+        };
 
         if self.data.len() < offset + length {
-            None
-        } else {
-            self.data.drain(..offset);
-            let data = self.data.drain(..length);
-            let buf = McBuf::from_bytes(data.as_slice());
-            Some(buf)
+            return Ok(None);
         }
+
+        let frame: Vec<u8> = self.data.drain(..offset + length).skip(offset).collect();
+        match self.compression_threshold {
+            Some(threshold) => decompress(&frame, threshold).map(Some),
+            None => Ok(Some(McBuf::from_bytes(&frame))),
+        }
+    }
+}
+
+fn decompress(frame: &[u8], threshold: usize) -> BufferResult<McBuf> {
+    let mut buf = McBuf::from_bytes(frame);
+    let data_length = buf.read_var_int()?;
+    if data_length == 0 {
+        return Ok(McBuf::from_bytes(buf.unread()));
+    }
+
+    let data_length = usize::try_from(data_length).map_err(|_| BufferError::NegativeLength)?;
+    if data_length < threshold {
+        return Err(BufferError::Generic("Compressed packet is below the compression threshold"));
+    }
+    if data_length > MAX_DECOMPRESSED_LENGTH {
+        return Err(BufferError::SizeLimitExceeded);
+    }
+
+    // Never inflate more than the packet claimed, so a small frame cannot expand without bound
+    let mut data = Vec::new();
+    ZlibDecoder::new(buf.unread())
+        .take(data_length as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|_| BufferError::Generic("Invalid zlib data"))?;
+    if data.len() != data_length {
+        return Err(BufferError::Generic("Decompressed packet length does not match"));
     }
-}
\ No newline at end of file
+
+    Ok(McBuf::from_bytes(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use leaflet_network_buffer::McBuf;
+    use crate::packet_writer::PacketWriter;
+    use super::{PacketReader, MAX_DECOMPRESSED_LENGTH};
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Frames a compressed body claiming `data_length` bytes once inflated.
+    fn compressed_frame(data_length: i32, compressed: &[u8]) -> Vec<u8> {
+        let mut body = McBuf::new();
+        body.write_var_int(data_length);
+        body.write_slice(compressed);
+        let mut frame = McBuf::new();
+        frame.write_var_int(body.length() as i32);
+        frame.write_slice(body.as_slice());
+        frame.as_slice().to_vec()
+    }
+
+    fn reader(threshold: usize, bytes: &[u8]) -> PacketReader {
+        let mut reader = PacketReader::new();
+        reader.set_compression(Some(threshold));
+        reader.append(bytes);
+        reader
+    }
+
+    #[test]
+    fn frame_below_threshold() {
+        let mut reader = reader(256, &[4, 0, 1, 2, 3]);
+        let packet = reader.read_packet().unwrap().unwrap();
+        assert_eq!(packet.as_slice(), &[1, 2, 3]);
+        assert!(reader.read_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn compressed_frame_is_inflated() {
+        let data = vec![7u8; 300];
+        let mut reader = reader(256, &compressed_frame(300, &zlib(&data)));
+        assert_eq!(reader.read_packet().unwrap().unwrap().as_slice(), data.as_slice());
+    }
+
+    #[test]
+    fn claimed_length_must_match() {
+        let data = vec![7u8; 300];
+        assert!(reader(256, &compressed_frame(301, &zlib(&data))).read_packet().is_err());
+        assert!(reader(256, &compressed_frame(299, &zlib(&data))).read_packet().is_err());
+    }
+
+    #[test]
+    fn compressed_below_threshold_is_rejected() {
+        let data = vec![7u8; 100];
+        assert!(reader(256, &compressed_frame(100, &zlib(&data))).read_packet().is_err());
+    }
+
+    #[test]
+    fn oversized_claim_is_rejected() {
+        let claimed = MAX_DECOMPRESSED_LENGTH as i32 + 1;
+        assert!(reader(256, &compressed_frame(claimed, &zlib(&[0; 16]))).read_packet().is_err());
+    }
+
+    #[test]
+    fn round_trip_with_writer() {
+        for threshold in [None, Some(0), Some(64), Some(1024)] {
+            let mut writer = PacketWriter::new();
+            writer.set_compression(threshold);
+            let mut reader = PacketReader::new();
+            reader.set_compression(threshold);
+
+            let packets: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 50 * i as usize + 1]).collect();
+            for packet in &packets {
+                reader.append(writer.write_packet(&McBuf::from_bytes(packet)).as_slice());
+            }
+            for packet in &packets {
+                assert_eq!(reader.read_packet().unwrap().unwrap().as_slice(), packet.as_slice());
+            }
+            assert!(reader.read_packet().unwrap().is_none());
+        }
+    }
+}
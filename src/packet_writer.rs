@@ -0,0 +1,51 @@
+use std::io::Write;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use leaflet_network_buffer::McBuf;
+
+pub struct PacketWriter {
+    compression_threshold: Option<usize>,
+}
+
+impl PacketWriter {
+    pub fn new() -> Self {
+        Self { compression_threshold: None }
+    }
+
+    /// Switches to the compressed frame format, or back if `threshold` is `None`.
+    /// Packets of at least `threshold` bytes are compressed.
+    pub fn set_compression(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Frames a packet's id and body for sending.
+    pub fn write_packet(&self, packet: &McBuf) -> McBuf {
+        let mut frame = McBuf::new();
+        let Some(threshold) = self.compression_threshold else {
+            frame.write_var_int(packet.length() as i32);
+            frame.write_slice(packet.as_slice());
+            return frame;
+        };
+
+        let mut body = McBuf::new();
+        if packet.length() >= threshold {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(packet.as_slice()).expect("Writing to a Vec cannot fail");
+            body.write_var_int(packet.length() as i32);
+            body.write_slice(&encoder.finish().expect("Writing to a Vec cannot fail"));
+        } else {
+            body.write_var_int(0);
+            body.write_slice(packet.as_slice());
+        }
+
+        frame.write_var_int(body.length() as i32);
+        frame.write_slice(body.as_slice());
+        frame
+    }
+}
+
+impl Default for PacketWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use rand::seq::IteratorRandom;
+use uuid::Uuid;
+use leaflet_protocol::version::ProtocolVersion;
+use leaflet_types::game_profile::GameProfile;
+use leaflet_types::server_status::{ServerStatus, StatusPlayer, StatusPlayers, StatusVersion};
+use leaflet_types::text_component::TextComponent;
+use crate::auth::ServerKeys;
+use crate::config::ServerConfig;
+
+/// How many players the server list shows when hovering the player count, like vanilla.
+const STATUS_SAMPLE_SIZE: usize = 12;
+
+/// State shared by all connections.
+pub struct Server {
+    pub config: ServerConfig,
+    pub keys: ServerKeys,
+    /// The favicon as a data URI, read once on startup.
+    favicon: Option<String>,
+    /// Names of the players that finished logging in, by UUID.
+    players: Mutex<HashMap<Uuid, String>>,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            favicon: load_favicon(&config.favicon),
+            config,
+            keys: ServerKeys::generate(),
+            players: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a player as online. Fails if a player with the same UUID or
+    /// name, ignoring case, is already online.
+    pub fn add_player(&self, profile: &GameProfile) -> bool {
+        let mut players = self.players.lock().unwrap_or_else(PoisonError::into_inner);
+        let taken = players.contains_key(&profile.uuid)
+            || players.values().any(|name| name.eq_ignore_ascii_case(&profile.username));
+        if taken {
+            return false;
+        }
+
+        players.insert(profile.uuid, profile.username.clone());
+        true
+    }
+
+    pub fn remove_player(&self, uuid: &Uuid) {
+        self.players.lock().unwrap_or_else(PoisonError::into_inner).remove(uuid);
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// The status shown in the server list, with a random sample of the online
+    /// players. Clients see the server as compatible if `version` is theirs.
+    pub fn status(&self, version: ProtocolVersion) -> ServerStatus {
+        let players = self.players.lock().unwrap_or_else(PoisonError::into_inner);
+        let sample = players
+            .iter()
+            .choose_multiple(&mut rand::thread_rng(), STATUS_SAMPLE_SIZE)
+            .into_iter()
+            .map(|(uuid, name)| StatusPlayer { name: name.clone(), id: *uuid })
+            .collect();
+
+        ServerStatus {
+            version: StatusVersion { name: ProtocolVersion::supported_range(), protocol: version.protocol() },
+            players: Some(StatusPlayers {
+                max: self.config.max_players as i32,
+                online: players.len() as i32,
+                sample,
+            }),
+            description: TextComponent::from_minimessage(&self.config.motd),
+            favicon: self.favicon.clone(),
+            enforces_secure_chat: self.config.enforce_secure_chat,
+        }
+    }
+}
+
+/// Reads a PNG favicon, which clients only accept at 64x64 pixels.
+fn load_favicon(path: impl AsRef<Path>) -> Option<String> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    let path = path.as_ref();
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            println!("Could not read the favicon {}: {e}", path.display());
+            return None;
+        }
+    };
+
+    // The IHDR chunk comes first, with the width and height right after its type
+    let size = bytes.get(16..24).map(|size| {
+        let (width, height) = size.split_at(4);
+        (u32::from_be_bytes(width.try_into().unwrap()), u32::from_be_bytes(height.try_into().unwrap()))
+    });
+    if !bytes.starts_with(PNG_SIGNATURE) || bytes.get(12..16) != Some(b"IHDR") {
+        println!("The favicon {} is not a PNG", path.display());
+        return None;
+    }
+    if size != Some((64, 64)) {
+        println!("The favicon {} must be 64x64 pixels", path.display());
+        return None;
+    }
+
+    Some(format!("data:image/png;base64,{}", STANDARD.encode(&bytes)))
+}